@@ -0,0 +1,15 @@
+use dotenv::var;
+
+///
+/// Read a number from an environment variable,
+/// falling back to `default` when it is missing or invalid
+///
+pub fn env_u64(name: &str, default: u64) -> u64 {
+    match var(name) {
+        Ok(value) => value.trim().parse::<u64>().unwrap_or_else(|_| {
+            eprintln!("Invalid {} value {}", name, value);
+            default
+        }),
+        Err(_) => default,
+    }
+}
@@ -0,0 +1,3 @@
+pub mod env;
+pub mod packages;
+pub mod prime_time;
@@ -0,0 +1,277 @@
+use crate::data::errors::IngestionError;
+use crate::data::sources::xmltv::{self, XmlTvSource};
+use dotenv::var;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Configuration file used when `PACKAGES_CONFIG` is not set
+const DEFAULT_CONFIG_PATH: &str = "packages.toml";
+
+///
+/// The packages imported on each refresh, in import order
+///
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PackagesConfig {
+    #[serde(rename = "package")]
+    pub packages: Vec<PackageConfig>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PackageConfig {
+    /// The package identifier used by the API (e.g. "TNT")
+    pub id: String,
+
+    /// The name displayed to the users
+    pub name: String,
+
+    /// A URL, a local path, or a file name relative to `XMLTV_BASE_URL`
+    pub source: String,
+
+    /// What the package contributes to the guide
+    #[serde(default)]
+    pub mode: PackageMode,
+
+    /// Whether the whole import is aborted when this package cannot be imported
+    #[serde(default)]
+    pub required: bool,
+
+    /// The file to read when the source is a zip archive, the first XML file by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+
+    /// URLs or paths tried in turn when the source cannot be fetched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageMode {
+    /// New channels and their programmes are imported, along with the package membership
+    #[default]
+    Programs,
+    /// Only the membership of channels imported by previous packages is recorded
+    Channels,
+}
+
+impl PackageConfig {
+    ///
+    /// Where the package is fetched from, failing when it is relative to an unset `XMLTV_BASE_URL`
+    ///
+    pub fn xmltv_source(&self) -> Result<XmlTvSource, IngestionError> {
+        if self.is_absolute() {
+            return Ok(XmlTvSource::parse(&self.source));
+        }
+        Ok(XmlTvSource::parse(&format!(
+            "{}{}",
+            xmltv::base_location()?,
+            self.source.trim()
+        )))
+    }
+
+    ///
+    /// The package mirrors, followed by the same file under each `XMLTV_MIRROR_URLS`
+    /// location when the source is relative to `XMLTV_BASE_URL`
+    ///
+    pub fn xmltv_mirrors(&self) -> Vec<XmlTvSource> {
+        let mut mirrors = self
+            .mirrors
+            .iter()
+            .map(|mirror| XmlTvSource::parse(mirror))
+            .collect::<Vec<XmlTvSource>>();
+        if !self.is_absolute() {
+            mirrors.extend(
+                xmltv::mirror_locations()
+                    .iter()
+                    .map(|base| XmlTvSource::parse(&format!("{}{}", base, self.source.trim()))),
+            );
+        }
+        mirrors
+    }
+
+    fn is_absolute(&self) -> bool {
+        let source = self.source.trim();
+        source.contains("://") || source.starts_with('/')
+    }
+}
+
+impl Default for PackagesConfig {
+    ///
+    /// The packages published by XML TV Fr
+    ///
+    fn default() -> Self {
+        PackagesConfig {
+            packages: vec![
+                PackageConfig {
+                    id: "ALL".to_string(),
+                    name: "Toutes les chaînes".to_string(),
+                    source: "xmltv.zip".to_string(),
+                    mode: PackageMode::Programs,
+                    required: true,
+                    entry: None,
+                    mirrors: vec![],
+                },
+                PackageConfig {
+                    id: "FR".to_string(),
+                    name: "Chaînes françaises".to_string(),
+                    source: "xmltv_fr.zip".to_string(),
+                    mode: PackageMode::Programs,
+                    required: false,
+                    entry: None,
+                    mirrors: vec![],
+                },
+                PackageConfig {
+                    id: "TNT".to_string(),
+                    name: "TNT".to_string(),
+                    source: "xmltv_tnt.zip".to_string(),
+                    mode: PackageMode::Channels,
+                    required: false,
+                    entry: None,
+                    mirrors: vec![],
+                },
+            ],
+        }
+    }
+}
+
+impl PackagesConfig {
+    pub fn parse(content: &str) -> Result<PackagesConfig, String> {
+        let config: PackagesConfig = toml::from_str(content).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !self
+            .packages
+            .iter()
+            .any(|p| p.mode == PackageMode::Programs)
+        {
+            return Err("At least one package must import programs".to_string());
+        }
+        let mut ids = HashSet::new();
+        for package in &self.packages {
+            if package.id.trim().is_empty() || package.source.trim().is_empty() {
+                return Err("Every package needs an id and a source".to_string());
+            }
+            if !ids.insert(package.id.as_str()) {
+                return Err(format!("Package {} is declared twice", package.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// Load the packages from the TOML file given by `PACKAGES_CONFIG`
+/// (`packages.toml` by default). The XML TV Fr packages are used
+/// when the default file does not exist.
+///
+pub fn load() -> Result<PackagesConfig, String> {
+    let path = var("PACKAGES_CONFIG");
+    let explicit = path.is_ok();
+    let path = path.unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+    match std::fs::read_to_string(&path) {
+        Ok(content) => PackagesConfig::parse(&content).map_err(|e| format!("{}: {}", path, e)),
+        Err(_) if !explicit => Ok(PackagesConfig::default()),
+        Err(e) => Err(format!("{}: {}", path, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_packages() {
+        // GIVEN
+        let content = r#"
+[[package]]
+id = "ALL"
+name = "Toutes les chaînes"
+source = "xmltv.zip"
+required = true
+entry = "xmltv.xml"
+
+[[package]]
+id = "CANAL"
+name = "Canal+"
+source = "https://example.com/canal.xml.gz"
+mode = "channels"
+mirrors = ["https://mirror.example.com/canal.xml.gz"]
+"#;
+
+        // WHEN
+        let config = PackagesConfig::parse(content).expect("Config should be valid");
+
+        // THEN
+        assert_eq!(config.packages.len(), 2);
+        assert_eq!(config.packages[0].mode, PackageMode::Programs);
+        assert!(config.packages[0].required);
+        assert_eq!(config.packages[0].entry.as_deref(), Some("xmltv.xml"));
+        assert_eq!(config.packages[1].mode, PackageMode::Channels);
+        assert!(!config.packages[1].required);
+        assert_eq!(
+            config.packages[1].xmltv_source(),
+            Ok(XmlTvSource::Url(
+                "https://example.com/canal.xml.gz".to_string()
+            ))
+        );
+        assert_eq!(
+            config.packages[1].xmltv_mirrors(),
+            vec![XmlTvSource::Url(
+                "https://mirror.example.com/canal.xml.gz".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_default_config_file_matches_builtin_packages() {
+        // WHEN
+        let config = PackagesConfig::parse(include_str!("../../packages.toml"))
+            .expect("packages.toml should be valid");
+
+        // THEN
+        assert_eq!(config, PackagesConfig::default());
+    }
+
+    #[test]
+    fn test_parse_rejects_duplicated_packages() {
+        // GIVEN
+        let content = r#"
+[[package]]
+id = "ALL"
+name = "All"
+source = "xmltv.zip"
+
+[[package]]
+id = "ALL"
+name = "All again"
+source = "xmltv.zip"
+"#;
+
+        // WHEN
+        let result = PackagesConfig::parse(content);
+
+        // THEN
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_channels_only() {
+        // GIVEN
+        let content = r#"
+[[package]]
+id = "TNT"
+name = "TNT"
+source = "xmltv_tnt.zip"
+mode = "channels"
+"#;
+
+        // WHEN
+        let result = PackagesConfig::parse(content);
+
+        // THEN
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::domain::entities::time_window::at_local_time;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use dotenv::var;
+
+/// Minimum duration of the parts of the evening when `PRIME_TIME_MIN_DURATION_MINUTES` is not set
+const DEFAULT_MIN_DURATION_MINUTES: i64 = 30;
+
+/// Until this hour, the night belongs to the evening of the day before
+const END_OF_NIGHT_HOUR: u32 = 6;
+
+/// Timezone of the prime time when `PRIME_TIME_TZ` is not set, the one of the guide
+const DEFAULT_TZ: Tz = chrono_tz::Europe::Paris;
+
+///
+/// When the evening programmes start, the first one of them being
+/// the "première partie de soirée" and the next one the "deuxième partie"
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrimeTime {
+    /// The local time from which the first part starts
+    pub time: NaiveTime,
+    /// Shorter programmes (news flashes, weather, ...) are not parts of the evening
+    pub min_duration: TimeDelta,
+    /// The timezone of `time`
+    pub tz: Tz,
+}
+
+impl Default for PrimeTime {
+    fn default() -> Self {
+        PrimeTime {
+            time: NaiveTime::from_hms_opt(20, 30, 0).unwrap_or_default(),
+            min_duration: TimeDelta::minutes(DEFAULT_MIN_DURATION_MINUTES),
+            tz: DEFAULT_TZ,
+        }
+    }
+}
+
+impl PrimeTime {
+    ///
+    /// Read the prime time from the `PRIME_TIME` (HH:MM), `PRIME_TIME_MIN_DURATION_MINUTES`
+    /// and `PRIME_TIME_TZ` environment variables
+    ///
+    pub fn from_env() -> PrimeTime {
+        let mut prime_time = PrimeTime::default();
+        if let Ok(time) = var("PRIME_TIME") {
+            match NaiveTime::parse_from_str(time.trim(), "%H:%M") {
+                Ok(parsed) => prime_time.time = parsed,
+                Err(e) => eprintln!("Invalid PRIME_TIME value {}: {}", time, e),
+            }
+        }
+        if let Ok(minutes) = var("PRIME_TIME_MIN_DURATION_MINUTES") {
+            match minutes.trim().parse::<i64>() {
+                Ok(parsed) if parsed >= 0 => prime_time.min_duration = TimeDelta::minutes(parsed),
+                _ => eprintln!("Invalid PRIME_TIME_MIN_DURATION_MINUTES value {}", minutes),
+            }
+        }
+        if let Ok(tz) = var("PRIME_TIME_TZ") {
+            match tz.trim().parse::<Tz>() {
+                Ok(parsed) => prime_time.tz = parsed,
+                Err(e) => eprintln!("Invalid PRIME_TIME_TZ value {}: {}", tz, e),
+            }
+        }
+        prime_time
+    }
+
+    ///
+    /// The day of the evening in progress or to come at `now`,
+    /// after midnight still the evening of the day before
+    ///
+    pub fn tonight(&self, now: DateTime<Utc>) -> NaiveDate {
+        let local = now.with_timezone(&self.tz).naive_local();
+        let end_of_night = NaiveTime::from_hms_opt(END_OF_NIGHT_HOUR, 0, 0).unwrap_or_default();
+        if local.time() < end_of_night {
+            local.date().pred_opt().unwrap_or(local.date())
+        } else {
+            local.date()
+        }
+    }
+
+    ///
+    /// When the evening of `date` starts
+    ///
+    pub fn start_on(&self, date: NaiveDate) -> DateTime<Utc> {
+        at_local_time(date, self.time, &self.tz)
+    }
+
+    ///
+    /// A human readable summary of the prime time, logged at startup
+    ///
+    pub fn describe(&self) -> String {
+        format!(
+            "{} ({}), programmes of at least {} minutes",
+            self.time.format("%H:%M"),
+            self.tz,
+            self.min_duration.num_minutes()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_tonight_in_the_evening() {
+        // GIVEN
+        // 19:00 in Paris
+        let now = Utc.with_ymd_and_hms(2024, 7, 14, 17, 0, 0).unwrap();
+
+        // WHEN
+        let date = PrimeTime::default().tonight(now);
+
+        // THEN
+        assert_eq!(date, NaiveDate::from_ymd_opt(2024, 7, 14).unwrap());
+        assert_eq!(
+            PrimeTime::default().start_on(date),
+            Utc.with_ymd_and_hms(2024, 7, 14, 18, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tonight_after_midnight() {
+        // GIVEN
+        // 00:30 in Paris, the 15th
+        let now = Utc.with_ymd_and_hms(2024, 7, 14, 22, 30, 0).unwrap();
+
+        // WHEN
+        let date = PrimeTime::default().tonight(now);
+
+        // THEN
+        assert_eq!(date, NaiveDate::from_ymd_opt(2024, 7, 14).unwrap());
+    }
+}
@@ -1,17 +1,57 @@
+use crate::data::errors::IngestionError;
 use crate::data::models::Channel as ChannelModel;
 use crate::domain::entities::channel::Channel as ChannelEntity;
+use crate::domain::entities::page::Paged;
+use crate::domain::entities::program::Program as ProgramEntity;
 
-pub fn model_to_entity(model: ChannelModel) -> ChannelEntity {
-    ChannelEntity {
+pub fn model_to_entity(model: ChannelModel) -> Result<ChannelEntity, IngestionError> {
+    if model.id.trim().is_empty() {
+        return Err(IngestionError::InvalidChannel {
+            channel_id: model.id,
+            reason: "missing channel id".to_string(),
+        });
+    }
+    Ok(ChannelEntity {
         id: 0,
         channel_id: model.id,
         name: model.display_name.content,
         icon_url: model.icon.map_or("".to_string(), |icon| icon.src),
+    })
+}
+
+///
+/// Convert the channel models, skipping the malformed ones
+///
+pub fn models_to_entities(models: Vec<ChannelModel>) -> (Vec<ChannelEntity>, Vec<IngestionError>) {
+    let mut entities = Vec::with_capacity(models.len());
+    let mut errors = Vec::new();
+    for model in models {
+        match model_to_entity(model) {
+            Ok(entity) => entities.push(entity),
+            Err(e) => errors.push(e),
+        }
     }
+    (entities, errors)
 }
 
-pub fn models_to_entities(models: Vec<ChannelModel>) -> Vec<ChannelEntity> {
-    models.into_iter().map(model_to_entity).collect()
+///
+/// Gather the rows of channels joined with their programmes, ordered by channel,
+/// into a page of channels with their programmes
+///
+pub fn rows_to_channels_with_programs(
+    rows: Vec<(ChannelEntity, Option<ProgramEntity>)>,
+    total: u64,
+) -> Paged<(ChannelEntity, Vec<ProgramEntity>)> {
+    let mut items: Vec<(ChannelEntity, Vec<ProgramEntity>)> = Vec::new();
+    for (channel, program) in rows {
+        match items.last_mut() {
+            Some((last, programs)) if last.channel_id == channel.channel_id => {
+                programs.extend(program)
+            }
+            _ => items.push((channel, program.into_iter().collect())),
+        }
+    }
+    Paged { items, total }
 }
 
 #[cfg(test)]
@@ -32,7 +72,7 @@ mod tests {
         };
 
         // WHEN
-        let entity = model_to_entity(model);
+        let entity = model_to_entity(model).expect("Channel should be valid");
 
         // THEN
         assert_eq!(&entity.channel_id, "channel123");
@@ -63,9 +103,10 @@ mod tests {
         ];
 
         // WHEN
-        let entities = models_to_entities(models);
+        let (entities, errors) = models_to_entities(models);
 
         // THEN
+        assert!(errors.is_empty());
         assert_eq!(entities.len(), 2);
         assert_eq!(&entities[0].channel_id, "channel1");
         assert_eq!(&entities[0].name, "Channel One");
@@ -74,4 +115,23 @@ mod tests {
         assert_eq!(&entities[1].name, "Channel Two");
         assert_eq!(&entities[1].icon_url, "");
     }
+
+    #[test]
+    fn test_models_to_entities_skips_missing_id() {
+        // GIVEN
+        let models = vec![ChannelModel {
+            id: " ".to_string(),
+            display_name: crate::data::models::DisplayName {
+                content: "No Id".to_string(),
+            },
+            icon: None,
+        }];
+
+        // WHEN
+        let (entities, errors) = models_to_entities(models);
+
+        // THEN
+        assert!(entities.is_empty());
+        assert_eq!(errors.len(), 1);
+    }
 }
@@ -0,0 +1,15 @@
+use crate::domain::entities::import_report::ImportReport;
+
+pub fn row_to_entity(row: &tokio_postgres::Row) -> Option<ImportReport> {
+    let report: serde_json::Value = row.get(1);
+    match serde_json::from_value::<ImportReport>(report) {
+        Ok(mut report) => {
+            report.id = row.get(0);
+            Some(report)
+        }
+        Err(e) => {
+            eprintln!("Unable to read import report: {}", e);
+            None
+        }
+    }
+}
@@ -1,2 +1,4 @@
 pub mod channel_converter;
+pub mod import_converter;
 pub mod program_converter;
+pub mod source_state_converter;
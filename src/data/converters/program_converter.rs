@@ -1,15 +1,47 @@
+use crate::data::errors::IngestionError;
 use crate::data::models::Program as ProgramModel;
 use crate::domain::entities::program::Program as ProgramEntity;
 use crate::domain::entities::rating::Rating;
-use chrono::DateTime;
+use chrono::{DateTime, FixedOffset};
 
-pub fn models_to_entities(models: Vec<ProgramModel>) -> Vec<ProgramEntity> {
-    models.into_iter().map(model_to_entity).collect()
+///
+/// Convert the program models, skipping the malformed ones
+///
+pub fn models_to_entities(models: Vec<ProgramModel>) -> (Vec<ProgramEntity>, Vec<IngestionError>) {
+    let mut entities = Vec::with_capacity(models.len());
+    let mut errors = Vec::new();
+    for model in models {
+        match model_to_entity(model) {
+            Ok(entity) => entities.push(entity),
+            Err(e) => errors.push(e),
+        }
+    }
+    (entities, errors)
+}
+
+fn parse_xmltv_time(
+    value: &str,
+    channel_id: &str,
+    field: &str,
+) -> Result<DateTime<FixedOffset>, IngestionError> {
+    DateTime::parse_from_str(value, "%Y%m%d%H%M%S %z").map_err(|e| IngestionError::InvalidProgram {
+        channel_id: channel_id.to_string(),
+        reason: format!("invalid {} time '{}': {}", field, value, e),
+    })
 }
 
-pub fn model_to_entity(model: ProgramModel) -> ProgramEntity {
+pub fn model_to_entity(model: ProgramModel) -> Result<ProgramEntity, IngestionError> {
+    let start_time = parse_xmltv_time(&model.start, &model.channel, "start")?;
+    let end_time = parse_xmltv_time(&model.stop, &model.channel, "stop")?;
+    if end_time <= start_time {
+        return Err(IngestionError::InvalidProgram {
+            channel_id: model.channel,
+            reason: format!("programme ends before it starts ({})", model.start),
+        });
+    }
+
     let icon_url = if let Some(icon) = model.icon {
-        icon.get(0).map_or(String::new(), |ic| ic.src.clone())
+        icon.first().map_or(String::new(), |ic| ic.src.clone())
     } else {
         String::new()
     };
@@ -41,23 +73,21 @@ pub fn model_to_entity(model: ProgramModel) -> ProgramEntity {
         String::new()
     };
 
-    let rating_system = if let Some(system) = model.rating.as_ref().map(|r| r.system.clone()) {
-        system
-    } else {
-        String::new()
-    };
+    let rating_system = model
+        .rating
+        .as_ref()
+        .map(|r| r.system.clone())
+        .unwrap_or_default();
 
-    ProgramEntity {
+    Ok(ProgramEntity {
         id: 0,
         channel_id: model.channel,
-        start_time: DateTime::parse_from_str(model.start.as_str(), "%Y%m%d%H%M%S %z")
-            .expect("Failed to parse start time"),
-        end_time: DateTime::parse_from_str(model.stop.as_str(), "%Y%m%d%H%M%S %z")
-            .expect("Failed to parse end time"),
+        start_time,
+        end_time,
         title: model.title,
-        sub_title: model.sub_title.and_then(|subs| subs.get(0).cloned()), // Take the first subtitle if exists
+        sub_title: model.sub_title.and_then(|subs| subs.first().cloned()), // Take the first subtitle if exists
         description: model.description.and_then(|desc| desc.content),
-        categories: Option::from(
+        categories: Some(
             categories
                 .split(",")
                 .map(|c| c.trim().to_string())
@@ -66,17 +96,15 @@ pub fn model_to_entity(model: ProgramModel) -> ProgramEntity {
         icon_url: Option::from(icon_url),
         episode_num,
         rating: Option::from(Rating {
-            system: Option::from(rating_system.clone()),
-            value: Option::from(rating_value.clone()),
-            icon: Option::from(rating_icon.clone()),
+            system: Some(rating_system),
+            value: rating_value,
+            icon: Some(rating_icon),
         }),
-    }
+    })
 }
 
-pub fn row_to_entity(row: &postgres::Row) -> ProgramEntity {
-    let row_categories: Option<String> = row.get(7);
-    let categories: Option<Vec<String>> =
-        row_categories.map(|c| c.split(',').map(|s| s.trim().to_string()).collect());
+pub fn row_to_entity(row: &tokio_postgres::Row) -> ProgramEntity {
+    let categories = split_categories(row.get(7));
     ProgramEntity {
         id: row.get(0),
         channel_id: row.get(1),
@@ -96,6 +124,40 @@ pub fn row_to_entity(row: &postgres::Row) -> ProgramEntity {
     }
 }
 
+///
+/// Convert a row of the SQLite programs table, whose times are Unix timestamps
+///
+pub fn sqlite_row_to_entity(row: &rusqlite::Row) -> rusqlite::Result<ProgramEntity> {
+    Ok(ProgramEntity {
+        id: row.get(0)?,
+        channel_id: row.get(1)?,
+        start_time: from_timestamp(row.get(2)?),
+        end_time: from_timestamp(row.get(3)?),
+        title: row.get(4)?,
+        sub_title: row.get(5)?,
+        description: row.get(6)?,
+        categories: split_categories(row.get(7)?),
+        icon_url: row.get(8)?,
+        episode_num: row.get(9)?,
+        rating: Some(Rating {
+            system: row.get(10)?,
+            value: row.get(11)?,
+            icon: row.get(12)?,
+        }),
+    })
+}
+
+fn split_categories(categories: Option<String>) -> Option<Vec<String>> {
+    categories.map(|c| c.split(',').map(|s| s.trim().to_string()).collect())
+}
+
+fn from_timestamp(seconds: i64) -> DateTime<chrono::FixedOffset> {
+    let naive = DateTime::from_timestamp(seconds, 0)
+        .unwrap_or_default()
+        .naive_utc();
+    from_naive_utc_and_offset(naive)
+}
+
 fn from_naive_utc_and_offset(naive: chrono::NaiveDateTime) -> DateTime<chrono::FixedOffset> {
     let offset = chrono::FixedOffset::east_opt(0).expect("Cannot create offset");
     DateTime::from_naive_utc_and_offset(naive, offset)
@@ -141,7 +203,7 @@ mod tests {
         };
 
         // WHEN
-        let entity = model_to_entity(model);
+        let entity = model_to_entity(model).expect("Program should be valid");
 
         // THEN
         assert_eq!(&entity.channel_id, "channel123");
@@ -170,4 +232,41 @@ mod tests {
             "http://example.com/rating_icon.png"
         );
     }
+
+    #[test]
+    fn test_models_to_entities_skips_invalid_times() {
+        // GIVEN
+        let models = vec![
+            minimal_model("Valid", "20240101080000 +0000", "20240101090000 +0000"),
+            minimal_model("Invalid start", "not a date", "20240101090000 +0000"),
+            minimal_model("Reversed", "20240101090000 +0000", "20240101080000 +0000"),
+        ];
+
+        // WHEN
+        let (entities, errors) = models_to_entities(models);
+
+        // THEN
+        assert_eq!(entities.len(), 1);
+        assert_eq!(&entities[0].title, "Valid");
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0],
+            IngestionError::InvalidProgram { channel_id, .. } if channel_id == "channel123"
+        ));
+    }
+
+    fn minimal_model(title: &str, start: &str, stop: &str) -> ProgramModel {
+        ProgramModel {
+            channel: "channel123".to_string(),
+            start: start.to_string(),
+            stop: stop.to_string(),
+            title: title.to_string(),
+            sub_title: None,
+            description: None,
+            categories: None,
+            icon: None,
+            episode_number: None,
+            rating: None,
+        }
+    }
 }
@@ -0,0 +1,10 @@
+use crate::domain::entities::source_state::SourceState;
+
+pub fn row_to_entity(row: &tokio_postgres::Row) -> SourceState {
+    SourceState {
+        source: row.get(0),
+        etag: row.get(1),
+        last_modified: row.get(2),
+        sha256: row.get(3),
+    }
+}
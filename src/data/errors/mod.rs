@@ -0,0 +1,189 @@
+use crate::domain::errors::RepositoryError;
+use std::fmt::{Display, Formatter};
+
+///
+/// Errors raised while ingesting XMLTV data
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum IngestionError {
+    /// The packages configuration or the environment is invalid
+    Config(String),
+    /// The XMLTV source could not be downloaded
+    Fetch(String),
+    /// The downloaded archive could not be read
+    Archive(String),
+    /// The XMLTV document could not be parsed
+    Parse(String),
+    /// A channel is malformed and has been skipped
+    InvalidChannel { channel_id: String, reason: String },
+    /// A programme is malformed and has been skipped
+    InvalidProgram { channel_id: String, reason: String },
+    /// The database rejected the import
+    Database(String),
+    /// A background task of the import panicked or was cancelled
+    Task(String),
+}
+
+impl Display for IngestionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IngestionError::Config(e) => write!(f, "Invalid configuration: {}", e),
+            IngestionError::Fetch(e) => write!(f, "Failed to fetch XMLTV data: {}", e),
+            IngestionError::Archive(e) => write!(f, "Failed to read XMLTV archive: {}", e),
+            IngestionError::Parse(e) => write!(f, "Failed to parse XMLTV data: {}", e),
+            IngestionError::InvalidChannel { channel_id, reason } => {
+                write!(f, "Skipped channel '{}': {}", channel_id, reason)
+            }
+            IngestionError::InvalidProgram { channel_id, reason } => {
+                write!(
+                    f,
+                    "Skipped programme on channel '{}': {}",
+                    channel_id, reason
+                )
+            }
+            IngestionError::Database(e) => write!(f, "Database error: {}", e),
+            IngestionError::Task(e) => write!(f, "Import task failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for IngestionError {}
+
+impl From<tokio_postgres::Error> for IngestionError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        IngestionError::Database(e.to_string())
+    }
+}
+
+impl From<RepositoryError> for IngestionError {
+    fn from(e: RepositoryError) -> Self {
+        IngestionError::Database(e.to_string())
+    }
+}
+
+///
+/// Errors raised while talking to the database
+///
+#[derive(Debug)]
+pub enum DatabaseError {
+    /// No connection could be obtained from the pool
+    Pool(bb8::RunError<tokio_postgres::Error>),
+    /// The database rejected the query
+    Query(tokio_postgres::Error),
+    /// The SQLite database rejected the query
+    Sqlite(rusqlite::Error),
+    /// The blocking task running the query panicked or was cancelled
+    Task(tokio::task::JoinError),
+}
+
+impl Display for DatabaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatabaseError::Pool(e) => write!(f, "No database connection available: {}", e),
+            DatabaseError::Query(e) => write!(f, "{}", e),
+            DatabaseError::Sqlite(e) => write!(f, "{}", e),
+            DatabaseError::Task(e) => write!(f, "Database task failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DatabaseError {}
+
+impl From<bb8::RunError<tokio_postgres::Error>> for DatabaseError {
+    fn from(e: bb8::RunError<tokio_postgres::Error>) -> Self {
+        DatabaseError::Pool(e)
+    }
+}
+
+impl From<tokio_postgres::Error> for DatabaseError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        DatabaseError::Query(e)
+    }
+}
+
+impl From<tokio::task::JoinError> for DatabaseError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        DatabaseError::Task(e)
+    }
+}
+
+impl From<rusqlite::Error> for DatabaseError {
+    fn from(e: rusqlite::Error) -> Self {
+        DatabaseError::Sqlite(e)
+    }
+}
+
+impl From<DatabaseError> for RepositoryError {
+    fn from(e: DatabaseError) -> Self {
+        if database_unreachable(&e) {
+            RepositoryError::Unavailable(e.to_string())
+        } else {
+            RepositoryError::Failed(e.to_string())
+        }
+    }
+}
+
+fn database_unreachable(e: &DatabaseError) -> bool {
+    match e {
+        DatabaseError::Pool(_) => true,
+        DatabaseError::Query(e) => e.is_closed(),
+        DatabaseError::Sqlite(e) => matches!(
+            e.sqlite_error_code(),
+            Some(
+                rusqlite::ErrorCode::DatabaseBusy
+                    | rusqlite::ErrorCode::DatabaseLocked
+                    | rusqlite::ErrorCode::CannotOpen
+            )
+        ),
+        // Only cancelled while the server shuts down
+        DatabaseError::Task(e) => e.is_cancelled(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_busy_database_is_unavailable() {
+        // GIVEN
+        let error = DatabaseError::Sqlite(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        ));
+
+        // WHEN
+        let repository_error = RepositoryError::from(error);
+
+        // THEN
+        assert!(matches!(repository_error, RepositoryError::Unavailable(_)));
+    }
+
+    #[test]
+    fn test_invalid_query_failed() {
+        // GIVEN
+        let error = DatabaseError::Sqlite(rusqlite::Error::InvalidQuery);
+
+        // WHEN
+        let repository_error = RepositoryError::from(error);
+
+        // THEN
+        assert!(matches!(repository_error, RepositoryError::Failed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_panicked_database_task_failed() {
+        // GIVEN
+        let error = DatabaseError::Task(
+            tokio::task::spawn_blocking(|| panic!("query panicked"))
+                .await
+                .unwrap_err(),
+        );
+
+        // WHEN
+        let repository_error = RepositoryError::from(error);
+
+        // THEN
+        assert!(matches!(repository_error, RepositoryError::Failed(_)));
+    }
+}
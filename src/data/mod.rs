@@ -1,4 +1,5 @@
 pub mod converters;
+pub mod errors;
 pub mod models;
 pub mod repositories;
 pub mod sources;
@@ -1,13 +1,5 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
-pub struct XmlTv {
-    #[serde(rename = "channel")]
-    pub channels: Vec<Channel>,
-    #[serde(rename = "programme")]
-    pub programs: Vec<Program>,
-}
-
 #[derive(Serialize, Deserialize)]
 pub struct Channel {
     #[serde(rename = "@id")]
@@ -1,9 +1,19 @@
-use crate::data::sources::db::postgres_client as postgres_client;
 use crate::domain::entities::channel::Channel;
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::ChannelRepository;
 
-pub fn get_channels_by_package(package: String) -> Vec<Channel> {
+///
+/// The channels of a package, every channel for the package ALL
+///
+pub async fn get_channels_by_package(
+    repository: &dyn ChannelRepository,
+    package: String,
+    page: PageRequest,
+) -> Result<Paged<Channel>, RepositoryError> {
     if package == "ALL" {
-        return postgres_client::find_all_channels();
+        repository.find_all_channels(page).await
+    } else {
+        repository.find_channels_by_package(&package, page).await
     }
-    postgres_client::find_channels_by_package(package)
 }
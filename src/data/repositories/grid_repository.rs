@@ -0,0 +1,134 @@
+use crate::data::repositories::channel_repository;
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::grid::GridChannels;
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::entities::program::Program;
+use crate::domain::entities::time_window::TimeWindow;
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::{ChannelRepository, ProgramRepository};
+use std::collections::HashMap;
+
+///
+/// A page of channels along with their programmes on air during `window`, by start time
+///
+pub async fn get_grid(
+    channel_repository: &dyn ChannelRepository,
+    program_repository: &dyn ProgramRepository,
+    channels: GridChannels,
+    window: TimeWindow,
+    page: PageRequest,
+) -> Result<Paged<(Channel, Vec<Program>)>, RepositoryError> {
+    let channels = match channels {
+        GridChannels::Package(package) => {
+            channel_repository::get_channels_by_package(channel_repository, package, page).await?
+        }
+        GridChannels::ChannelIds(channel_ids) => {
+            channel_repository
+                .find_channels_by_ids(&channel_ids, page)
+                .await?
+        }
+    };
+    let channel_ids: Vec<String> = channels
+        .items
+        .iter()
+        .map(|channel| channel.channel_id.clone())
+        .collect();
+    let mut programs: HashMap<String, Vec<Program>> = HashMap::new();
+    for program in program_repository
+        .find_programs_by_channel_ids(&channel_ids, window)
+        .await?
+    {
+        programs
+            .entry(program.channel_id.clone())
+            .or_default()
+            .push(program);
+    }
+    Ok(Paged {
+        items: channels
+            .items
+            .into_iter()
+            .map(|channel| {
+                let programs = programs.remove(&channel.channel_id).unwrap_or_default();
+                (channel, programs)
+            })
+            .collect(),
+        total: channels.total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::repositories::memory_repository::MemoryRepository;
+    use crate::domain::entities::test_fixtures::{channel, program, time};
+    use crate::domain::repositories::GuideWriter;
+    use chrono::Utc;
+
+    #[tokio::test]
+    async fn test_grid_of_a_package() {
+        // GIVEN
+        let repository = MemoryRepository::default();
+        let channels = vec![channel("TF1.fr"), channel("France2.fr"), channel("M6.fr")];
+        repository.stage_channels(&channels).await.unwrap();
+        repository
+            .stage_channel_packages(&channels, "ALL")
+            .await
+            .unwrap();
+        repository
+            .stage_programs(&[
+                Program {
+                    end_time: time(21, 0),
+                    ..program("TF1.fr", time(19, 0), "News")
+                },
+                Program {
+                    end_time: time(23, 0),
+                    ..program("TF1.fr", time(21, 0), "Movie")
+                },
+                program("France2.fr", time(23, 0), "Late show"),
+                program("M6.fr", time(20, 0), "Cooking"),
+            ])
+            .await
+            .unwrap();
+        repository.publish_staging(&[]).await.unwrap();
+        let window = TimeWindow {
+            from: time(20, 0).with_timezone(&Utc),
+            to: Some(time(22, 0).with_timezone(&Utc)),
+        };
+        let page = PageRequest { page: 0, size: 2 };
+
+        // WHEN
+        let grid = get_grid(
+            &repository,
+            &repository,
+            GridChannels::Package("ALL".to_string()),
+            window,
+            page,
+        )
+        .await
+        .unwrap();
+
+        // THEN
+        assert_eq!(grid.total, 3);
+        assert_eq!(grid.items[0].0.channel_id, "France2.fr");
+        assert!(grid.items[0].1.is_empty());
+        assert_eq!(grid.items[1].0.channel_id, "M6.fr");
+        assert_eq!(grid.items[1].1[0].title, "Cooking");
+        let by_ids = get_grid(
+            &repository,
+            &repository,
+            GridChannels::ChannelIds(vec!["TF1.fr".to_string()]),
+            window,
+            page,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            by_ids.items[0]
+                .1
+                .iter()
+                .map(|p| p.title.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["News", "Movie"]
+        );
+    }
+}
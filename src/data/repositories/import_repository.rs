@@ -0,0 +1,20 @@
+use crate::domain::entities::import_report::ImportReport;
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::ImportRepository;
+
+/// Number of imports returned when no limit is given
+const DEFAULT_IMPORTS_LIMIT: i64 = 50;
+
+pub async fn get_imports(
+    repository: &dyn ImportRepository,
+    limit: Option<i64>,
+) -> Result<Vec<ImportReport>, RepositoryError> {
+    let limit = limit.unwrap_or(DEFAULT_IMPORTS_LIMIT).clamp(1, 1000);
+    repository.find_imports(limit).await
+}
+
+pub async fn get_latest_import(
+    repository: &dyn ImportRepository,
+) -> Result<Option<ImportReport>, RepositoryError> {
+    Ok(repository.find_imports(1).await?.into_iter().next())
+}
@@ -0,0 +1,628 @@
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::import_report::{GuideChanges, ImportReport};
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::entities::program::Program;
+use crate::domain::entities::rating::Rating;
+use crate::domain::entities::source_state::SourceState;
+use crate::domain::entities::time_window::TimeWindow;
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::{
+    ChannelRepository, GuideWriter, ImportRepository, ProgramRepository,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+///
+/// The guide kept in memory, lost on restart.
+/// Suited to tests and to small deployments without a database.
+///
+#[derive(Default)]
+pub struct MemoryRepository {
+    store: RwLock<MemoryStore>,
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    /// Published channels by channel id
+    channels: BTreeMap<String, Channel>,
+    /// Published channel ids by package
+    packages: HashMap<String, BTreeSet<String>>,
+    /// Published programmes by channel id and start time
+    programs: HashMap<String, BTreeMap<DateTime<FixedOffset>, Program>>,
+    staging: Staging,
+    imports: Vec<ImportReport>,
+    source_states: Vec<SourceState>,
+    last_channel_id: i32,
+    last_program_id: i32,
+    last_import_id: i32,
+}
+
+#[derive(Default)]
+struct Staging {
+    channels: Vec<Channel>,
+    /// Package and channel id of each membership
+    packages: Vec<(String, String)>,
+    programs: Vec<Program>,
+}
+
+impl MemoryRepository {
+    fn read(&self) -> RwLockReadGuard<'_, MemoryStore> {
+        self.store.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, MemoryStore> {
+        self.store.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl MemoryStore {
+    fn channel_programs(&self, channel_id: &str) -> impl Iterator<Item = &Program> {
+        self.programs
+            .get(channel_id)
+            .into_iter()
+            .flat_map(|programs| programs.values())
+    }
+
+    ///
+    /// Replace the published channels with the staged ones, keeping their ids
+    ///
+    fn publish_channels(&mut self) {
+        let mut channels = BTreeMap::new();
+        for staged in self.staging.channels.drain(..) {
+            if channels.contains_key(&staged.channel_id) {
+                continue;
+            }
+            let id = match self.channels.get(&staged.channel_id) {
+                Some(channel) => channel.id,
+                None => {
+                    self.last_channel_id += 1;
+                    self.last_channel_id
+                }
+            };
+            channels.insert(staged.channel_id.clone(), Channel { id, ..staged });
+        }
+        self.channels = channels;
+
+        self.packages.clear();
+        for (package, channel_id) in self.staging.packages.drain(..) {
+            self.packages.entry(package).or_default().insert(channel_id);
+        }
+    }
+
+    ///
+    /// Replace the published programmes with the staged ones, keeping the ids
+    /// of the programmes starting at the same time on the same channel
+    ///
+    fn publish_programs(&mut self) -> GuideChanges {
+        let mut changes = GuideChanges::default();
+        let mut programs: HashMap<String, BTreeMap<DateTime<FixedOffset>, Program>> =
+            HashMap::new();
+        for staged in self.staging.programs.drain(..) {
+            let channel_programs = programs.entry(staged.channel_id.clone()).or_default();
+            if channel_programs.contains_key(&staged.start_time) {
+                continue;
+            }
+            let previous = self
+                .programs
+                .get_mut(&staged.channel_id)
+                .and_then(|p| p.remove(&staged.start_time));
+            let program = match previous {
+                Some(previous) => {
+                    let program = Program {
+                        id: previous.id,
+                        ..staged
+                    };
+                    if program != previous {
+                        changes.updated += 1;
+                    }
+                    program
+                }
+                None => {
+                    changes.added += 1;
+                    self.last_program_id += 1;
+                    Program {
+                        id: self.last_program_id,
+                        ..staged
+                    }
+                }
+            };
+            channel_programs.insert(program.start_time, program);
+        }
+        // What is left of the previous guide is no longer in the sources
+        changes.deleted = self.programs.values().map(|p| p.len()).sum();
+        self.programs = programs;
+        changes
+    }
+}
+
+///
+/// The items of the requested page, out of all the items of a list
+///
+fn paginate<'a, T: Clone + 'a>(items: impl Iterator<Item = &'a T>, page: PageRequest) -> Paged<T> {
+    let items: Vec<&T> = items.collect();
+    Paged {
+        total: items.len() as u64,
+        items: items
+            .into_iter()
+            .skip(page.offset() as usize)
+            .take(page.size as usize)
+            .cloned()
+            .collect(),
+    }
+}
+
+#[async_trait]
+impl ChannelRepository for MemoryRepository {
+    async fn find_all_channels(
+        &self,
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        Ok(paginate(self.read().channels.values(), page))
+    }
+
+    async fn find_channels_by_package(
+        &self,
+        package: &str,
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        let store = self.read();
+        let channels = store
+            .packages
+            .get(package)
+            .into_iter()
+            .flatten()
+            .filter_map(|channel_id| store.channels.get(channel_id));
+        Ok(paginate(channels, page))
+    }
+
+    async fn find_channels_by_ids(
+        &self,
+        channel_ids: &[String],
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        let store = self.read();
+        let channel_ids: BTreeSet<&String> = channel_ids.iter().collect();
+        let channels = channel_ids
+            .into_iter()
+            .filter_map(|channel_id| store.channels.get(channel_id));
+        Ok(paginate(channels, page))
+    }
+}
+
+#[async_trait]
+impl ProgramRepository for MemoryRepository {
+    async fn find_programs_by_channel_id(
+        &self,
+        channel_id: &str,
+        window: TimeWindow,
+        page: PageRequest,
+    ) -> Result<Paged<Program>, RepositoryError> {
+        let store = self.read();
+        let programs = store
+            .channel_programs(channel_id)
+            .filter(|p| p.end_time > window.from && window.to.is_none_or(|to| p.start_time < to));
+        Ok(paginate(programs, page))
+    }
+
+    async fn find_programs_by_channel_ids(
+        &self,
+        channel_ids: &[String],
+        window: TimeWindow,
+    ) -> Result<Vec<Program>, RepositoryError> {
+        let store = self.read();
+        let channel_ids: BTreeSet<&String> = channel_ids.iter().collect();
+        Ok(channel_ids
+            .into_iter()
+            .flat_map(|channel_id| store.channel_programs(channel_id))
+            .filter(|p| p.end_time > window.from && window.to.is_none_or(|to| p.start_time < to))
+            .cloned()
+            .collect())
+    }
+
+    async fn find_channels_with_upcoming_programs(
+        &self,
+        package: Option<&str>,
+        at: DateTime<Utc>,
+        count: u32,
+        page: PageRequest,
+    ) -> Result<Paged<(Channel, Vec<Program>)>, RepositoryError> {
+        let store = self.read();
+        let channels: Vec<&Channel> = match package {
+            Some(package) => store
+                .packages
+                .get(package)
+                .into_iter()
+                .flatten()
+                .filter_map(|channel_id| store.channels.get(channel_id))
+                .collect(),
+            None => store.channels.values().collect(),
+        };
+        let page = paginate(channels.into_iter(), page);
+        Ok(Paged {
+            items: page
+                .items
+                .into_iter()
+                .map(|channel| {
+                    let programs = store
+                        .channel_programs(&channel.channel_id)
+                        .filter(|p| p.end_time > at)
+                        .take(count as usize)
+                        .cloned()
+                        .collect();
+                    (channel, programs)
+                })
+                .collect(),
+            total: page.total,
+        })
+    }
+
+    async fn find_current_program_by_channel_id(
+        &self,
+        channel_id: &str,
+    ) -> Result<Option<Program>, RepositoryError> {
+        let now = Utc::now();
+        Ok(self
+            .read()
+            .channel_programs(channel_id)
+            .find(|p| p.start_time <= now && p.end_time >= now)
+            .cloned())
+    }
+
+    async fn find_tonight_programs_by_channel_id(
+        &self,
+        channel_id: &str,
+        from: DateTime<Utc>,
+        min_duration: TimeDelta,
+        count: u32,
+    ) -> Result<Vec<Program>, RepositoryError> {
+        Ok(self
+            .read()
+            .channel_programs(channel_id)
+            .filter(|p| p.start_time >= from && p.end_time - p.start_time >= min_duration)
+            .take(count as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn search_programs(
+        &self,
+        query: &str,
+        page: PageRequest,
+    ) -> Result<Paged<Program>, RepositoryError> {
+        let query = query.to_lowercase();
+        let matches =
+            |text: Option<&String>| text.is_some_and(|t| t.to_lowercase().contains(&query));
+        let store = self.read();
+        let mut programs: Vec<&Program> = store
+            .programs
+            .values()
+            .flat_map(|programs| programs.values())
+            .filter(|p| {
+                matches(Some(&p.title))
+                    || matches(p.sub_title.as_ref())
+                    || matches(p.description.as_ref())
+            })
+            .collect();
+        programs.sort_by(|a, b| (&a.channel_id, a.start_time).cmp(&(&b.channel_id, b.start_time)));
+        Ok(paginate(programs.into_iter(), page))
+    }
+}
+
+#[async_trait]
+impl ImportRepository for MemoryRepository {
+    async fn save_import(&self, report: &ImportReport) -> Result<i32, RepositoryError> {
+        let mut store = self.write();
+        store.last_import_id += 1;
+        let id = store.last_import_id;
+        store.imports.push(ImportReport {
+            id,
+            ..report.clone()
+        });
+        Ok(id)
+    }
+
+    async fn find_imports(&self, limit: i64) -> Result<Vec<ImportReport>, RepositoryError> {
+        let mut imports = self.read().imports.clone();
+        imports.sort_by_key(|import| Reverse(import.started_at));
+        imports.truncate(limit.max(0) as usize);
+        Ok(imports)
+    }
+}
+
+#[async_trait]
+impl GuideWriter for MemoryRepository {
+    async fn find_source_states(&self) -> Result<Vec<SourceState>, RepositoryError> {
+        Ok(self.read().source_states.clone())
+    }
+
+    async fn clear_staging(&self) -> Result<(), RepositoryError> {
+        self.write().staging = Staging::default();
+        Ok(())
+    }
+
+    async fn stage_channels(&self, channels: &[Channel]) -> Result<(), RepositoryError> {
+        self.write()
+            .staging
+            .channels
+            .extend(channels.iter().cloned());
+        Ok(())
+    }
+
+    async fn stage_channel_packages(
+        &self,
+        channels: &[Channel],
+        package: &str,
+    ) -> Result<(), RepositoryError> {
+        self.write().staging.packages.extend(
+            channels
+                .iter()
+                .map(|c| (package.to_string(), c.channel_id.clone())),
+        );
+        Ok(())
+    }
+
+    async fn stage_programs(&self, programs: &[Program]) -> Result<(), RepositoryError> {
+        self.write()
+            .staging
+            .programs
+            .extend(programs.iter().map(stored_program));
+        Ok(())
+    }
+
+    async fn discard_staged_package(
+        &self,
+        package: &str,
+        channel_ids: &[String],
+    ) -> Result<(), RepositoryError> {
+        let mut store = self.write();
+        let staging = &mut store.staging;
+        staging.packages.retain(|(p, _)| p != package);
+        staging
+            .channels
+            .retain(|c| !channel_ids.contains(&c.channel_id));
+        staging
+            .programs
+            .retain(|p| !channel_ids.contains(&p.channel_id));
+        Ok(())
+    }
+
+    async fn publish_staging(
+        &self,
+        source_states: &[SourceState],
+    ) -> Result<GuideChanges, RepositoryError> {
+        let mut store = self.write();
+        store.publish_channels();
+        let changes = store.publish_programs();
+        store.source_states = source_states.to_vec();
+        println!(
+            "Staged guide published: {} programs added, {} updated, {} deleted.",
+            changes.added, changes.updated, changes.deleted
+        );
+        Ok(changes)
+    }
+}
+
+///
+/// The programme as the databases give it back:
+/// in UTC, with the missing values as empty strings
+///
+fn stored_program(program: &Program) -> Program {
+    let utc = FixedOffset::east_opt(0).expect("Cannot create offset");
+    let text = |value: &Option<String>| Some(value.clone().unwrap_or_default());
+    let rating = program.rating.as_ref();
+    Program {
+        start_time: program.start_time.with_timezone(&utc),
+        end_time: program.end_time.with_timezone(&utc),
+        sub_title: text(&program.sub_title),
+        description: text(&program.description),
+        categories: match program.categories.as_deref() {
+            Some(categories) if !categories.is_empty() => Some(categories.to_vec()),
+            _ => Some(vec![String::new()]),
+        },
+        icon_url: text(&program.icon_url),
+        episode_num: text(&program.episode_num),
+        rating: Some(Rating {
+            system: text(&rating.and_then(|r| r.system.clone())),
+            value: text(&rating.and_then(|r| r.value.clone())),
+            icon: text(&rating.and_then(|r| r.icon.clone())),
+        }),
+        ..program.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::test_fixtures::{channel, program, time};
+
+    /// A page holding every item of the tests
+    const ALL: PageRequest = PageRequest { page: 0, size: 100 };
+
+    async fn publish(
+        repository: &MemoryRepository,
+        channels: &[Channel],
+        programs: &[Program],
+    ) -> GuideChanges {
+        repository.clear_staging().await.unwrap();
+        repository.stage_channels(channels).await.unwrap();
+        repository
+            .stage_channel_packages(channels, "ALL")
+            .await
+            .unwrap();
+        repository.stage_programs(programs).await.unwrap();
+        repository.publish_staging(&[]).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_publish_keeps_ids_and_counts_changes() {
+        // GIVEN
+        let repository = MemoryRepository::default();
+        let channels = vec![channel("TF1.fr")];
+        publish(
+            &repository,
+            &channels,
+            &[
+                program("TF1.fr", time(20, 0), "News"),
+                program("TF1.fr", time(21, 0), "Movie"),
+                program("TF1.fr", time(23, 0), "Late show"),
+            ],
+        )
+        .await;
+        let first_ids = repository
+            .search_programs("", ALL)
+            .await
+            .unwrap()
+            .items
+            .iter()
+            .map(|p| (p.title.clone(), p.id))
+            .collect::<HashMap<String, i32>>();
+
+        // WHEN
+        let changes = publish(
+            &repository,
+            &channels,
+            &[
+                program("TF1.fr", time(20, 0), "News"),
+                program("TF1.fr", time(21, 0), "Another movie"),
+                program("TF1.fr", time(22, 0), "Documentary"),
+            ],
+        )
+        .await;
+
+        // THEN
+        assert_eq!(
+            changes,
+            GuideChanges {
+                added: 1,
+                updated: 1,
+                deleted: 1
+            }
+        );
+        let programs = repository.search_programs("", ALL).await.unwrap().items;
+        assert_eq!(programs.len(), 3);
+        let news = programs.iter().find(|p| p.title == "News").unwrap();
+        assert_eq!(news.id, first_ids["News"]);
+        let movie = programs
+            .iter()
+            .find(|p| p.title == "Another movie")
+            .unwrap();
+        assert_eq!(movie.id, first_ids["Movie"]);
+    }
+
+    #[tokio::test]
+    async fn test_discard_staged_package() {
+        // GIVEN
+        let repository = MemoryRepository::default();
+        repository.clear_staging().await.unwrap();
+        repository
+            .stage_channels(&[channel("TF1.fr")])
+            .await
+            .unwrap();
+        repository
+            .stage_channel_packages(&[channel("TF1.fr")], "ALL")
+            .await
+            .unwrap();
+        repository
+            .stage_channels(&[channel("RTL.be")])
+            .await
+            .unwrap();
+        repository
+            .stage_channel_packages(&[channel("TF1.fr"), channel("RTL.be")], "BE")
+            .await
+            .unwrap();
+
+        // WHEN
+        repository
+            .discard_staged_package("BE", &["RTL.be".to_string()])
+            .await
+            .unwrap();
+        repository.publish_staging(&[]).await.unwrap();
+
+        // THEN
+        let channels = repository.find_all_channels(ALL).await.unwrap().items;
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].channel_id, "TF1.fr");
+        assert!(
+            repository
+                .find_channels_by_package("BE", ALL)
+                .await
+                .unwrap()
+                .items
+                .is_empty()
+        );
+        assert_eq!(
+            repository
+                .find_channels_by_package("ALL", ALL)
+                .await
+                .unwrap()
+                .total,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_tonight_programs() {
+        // GIVEN
+        let repository = MemoryRepository::default();
+        let mut short = program("TF1.fr", time(20, 0), "Weather");
+        short.end_time = short.start_time + TimeDelta::minutes(10);
+        publish(
+            &repository,
+            &[channel("TF1.fr")],
+            &[
+                program("TF1.fr", time(19, 0), "News"),
+                short,
+                program("TF1.fr", time(21, 0), "Movie"),
+                program("TF1.fr", time(22, 0), "Documentary"),
+                program("TF1.fr", time(23, 0), "Late show"),
+            ],
+        )
+        .await;
+        let from = time(19, 30).with_timezone(&Utc);
+
+        // WHEN
+        let programs = repository
+            .find_tonight_programs_by_channel_id("TF1.fr", from, TimeDelta::minutes(30), 2)
+            .await
+            .unwrap();
+
+        // THEN
+        assert_eq!(
+            programs
+                .iter()
+                .map(|p| p.title.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["Movie", "Documentary"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_values_are_given_back_as_empty_strings() {
+        // GIVEN
+        let repository = MemoryRepository::default();
+        publish(
+            &repository,
+            &[channel("TF1.fr")],
+            &[program("TF1.fr", time(20, 0), "News")],
+        )
+        .await;
+
+        // WHEN
+        let programs = repository.search_programs("News", ALL).await.unwrap();
+
+        // THEN
+        let json = serde_json::to_value(&programs.items[0]).unwrap();
+        assert_eq!(json["subTitle"], "");
+        assert_eq!(json["description"], "");
+        assert_eq!(json["categories"], serde_json::json!([""]));
+        assert_eq!(json["iconUrl"], "");
+        assert_eq!(json["episodeNum"], "");
+        assert_eq!(
+            json["rating"],
+            serde_json::json!({"system": "", "value": "", "icon": ""})
+        );
+    }
+}
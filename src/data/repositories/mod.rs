@@ -1,3 +1,11 @@
 pub mod channel_repository;
+pub mod grid_repository;
+pub mod import_repository;
+pub mod memory_repository;
+pub mod now_repository;
+pub mod package_repository;
+pub mod postgres_repository;
 pub mod program_repository;
+pub mod sqlite_repository;
+pub mod storage;
 pub mod xml_tv_repository;
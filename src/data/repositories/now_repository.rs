@@ -0,0 +1,92 @@
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::entities::program::Program;
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::ProgramRepository;
+use chrono::{DateTime, Utc};
+
+///
+/// A page of the channels of a package, every channel for the package ALL,
+/// each with the programme on air at `now` and the `next` following ones,
+/// fetched in a single query
+///
+pub async fn get_package_now(
+    program_repository: &dyn ProgramRepository,
+    package: String,
+    now: DateTime<Utc>,
+    next: u32,
+    page: PageRequest,
+) -> Result<Paged<(Channel, Option<Program>, Vec<Program>)>, RepositoryError> {
+    let package = Some(package).filter(|package| package != "ALL");
+    // The programme on air, if any, comes first
+    let channels = program_repository
+        .find_channels_with_upcoming_programs(package.as_deref(), now, next + 1, page)
+        .await?;
+    Ok(Paged {
+        items: channels
+            .items
+            .into_iter()
+            .map(|(channel, mut upcoming)| {
+                let current = match upcoming.first() {
+                    Some(first) if first.start_time <= now => Some(upcoming.remove(0)),
+                    _ => None,
+                };
+                upcoming.truncate(next as usize);
+                (channel, current, upcoming)
+            })
+            .collect(),
+        total: channels.total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::repositories::memory_repository::MemoryRepository;
+    use crate::domain::entities::test_fixtures::{channel, program, time};
+    use crate::domain::repositories::GuideWriter;
+
+    #[tokio::test]
+    async fn test_now_and_next_of_a_package() {
+        // GIVEN
+        let repository = MemoryRepository::default();
+        let channels = vec![channel("TF1.fr"), channel("M6.fr")];
+        repository.stage_channels(&channels).await.unwrap();
+        repository
+            .stage_channel_packages(&channels, "TNT")
+            .await
+            .unwrap();
+        repository
+            .stage_programs(&[
+                program("TF1.fr", time(19, 0), "Weather"),
+                program("TF1.fr", time(20, 0), "News"),
+                program("TF1.fr", time(21, 0), "Movie"),
+                program("TF1.fr", time(22, 0), "Late show"),
+                program("M6.fr", time(21, 0), "Cooking"),
+            ])
+            .await
+            .unwrap();
+        repository.publish_staging(&[]).await.unwrap();
+        let now = time(20, 15).with_timezone(&Utc);
+        let page = PageRequest { page: 0, size: 10 };
+
+        // WHEN
+        let channels = get_package_now(&repository, "TNT".to_string(), now, 2, page)
+            .await
+            .unwrap();
+
+        // THEN
+        let rows = channels.items;
+        assert_eq!(channels.total, 2);
+        let (m6, current, next) = &rows[0];
+        assert_eq!(m6.channel_id, "M6.fr");
+        assert!(current.is_none());
+        assert_eq!(next[0].title, "Cooking");
+        let (_, current, next) = &rows[1];
+        assert_eq!(current.as_ref().unwrap().title, "News");
+        assert_eq!(
+            next.iter().map(|p| p.title.as_str()).collect::<Vec<&str>>(),
+            vec!["Movie", "Late show"]
+        );
+    }
+}
@@ -0,0 +1,16 @@
+use crate::config::packages::PackagesConfig;
+use crate::domain::entities::package::Package;
+
+///
+/// The packages of the configuration loaded at startup
+///
+pub fn get_packages(config: &PackagesConfig) -> Vec<Package> {
+    config
+        .packages
+        .iter()
+        .map(|p| Package {
+            id: p.id.clone(),
+            name: p.name.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,174 @@
+use crate::data::sources::db::pool::DbPool;
+use crate::data::sources::db::postgres_client;
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::import_report::{GuideChanges, ImportReport};
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::entities::program::Program;
+use crate::domain::entities::source_state::SourceState;
+use crate::domain::entities::time_window::TimeWindow;
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::{
+    ChannelRepository, GuideWriter, ImportRepository, ProgramRepository,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+
+///
+/// The guide stored in PostgreSQL
+///
+pub struct PostgresRepository {
+    pool: DbPool,
+}
+
+impl PostgresRepository {
+    pub fn new(pool: DbPool) -> PostgresRepository {
+        PostgresRepository { pool }
+    }
+}
+
+#[async_trait]
+impl ChannelRepository for PostgresRepository {
+    async fn find_all_channels(
+        &self,
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        Ok(postgres_client::find_all_channels(&self.pool, page).await?)
+    }
+
+    async fn find_channels_by_package(
+        &self,
+        package: &str,
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        Ok(postgres_client::find_channels_by_package(&self.pool, package, page).await?)
+    }
+
+    async fn find_channels_by_ids(
+        &self,
+        channel_ids: &[String],
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        Ok(postgres_client::find_channels_by_ids(&self.pool, channel_ids, page).await?)
+    }
+}
+
+#[async_trait]
+impl ProgramRepository for PostgresRepository {
+    async fn find_programs_by_channel_id(
+        &self,
+        channel_id: &str,
+        window: TimeWindow,
+        page: PageRequest,
+    ) -> Result<Paged<Program>, RepositoryError> {
+        Ok(
+            postgres_client::find_programs_by_channel_id(&self.pool, channel_id, window, page)
+                .await?,
+        )
+    }
+
+    async fn find_programs_by_channel_ids(
+        &self,
+        channel_ids: &[String],
+        window: TimeWindow,
+    ) -> Result<Vec<Program>, RepositoryError> {
+        Ok(postgres_client::find_programs_by_channel_ids(&self.pool, channel_ids, window).await?)
+    }
+
+    async fn find_channels_with_upcoming_programs(
+        &self,
+        package: Option<&str>,
+        at: DateTime<Utc>,
+        count: u32,
+        page: PageRequest,
+    ) -> Result<Paged<(Channel, Vec<Program>)>, RepositoryError> {
+        Ok(postgres_client::find_channels_with_upcoming_programs(
+            &self.pool, package, at, count, page,
+        )
+        .await?)
+    }
+
+    async fn find_current_program_by_channel_id(
+        &self,
+        channel_id: &str,
+    ) -> Result<Option<Program>, RepositoryError> {
+        Ok(postgres_client::find_current_program_by_channel_id(&self.pool, channel_id).await?)
+    }
+
+    async fn find_tonight_programs_by_channel_id(
+        &self,
+        channel_id: &str,
+        from: DateTime<Utc>,
+        min_duration: TimeDelta,
+        count: u32,
+    ) -> Result<Vec<Program>, RepositoryError> {
+        Ok(postgres_client::find_tonight_programs_by_channel_id(
+            &self.pool,
+            channel_id,
+            from,
+            min_duration,
+            count,
+        )
+        .await?)
+    }
+
+    async fn search_programs(
+        &self,
+        query: &str,
+        page: PageRequest,
+    ) -> Result<Paged<Program>, RepositoryError> {
+        Ok(postgres_client::search_programs(&self.pool, query, page).await?)
+    }
+}
+
+#[async_trait]
+impl ImportRepository for PostgresRepository {
+    async fn save_import(&self, report: &ImportReport) -> Result<i32, RepositoryError> {
+        Ok(postgres_client::save_import(&self.pool, report).await?)
+    }
+
+    async fn find_imports(&self, limit: i64) -> Result<Vec<ImportReport>, RepositoryError> {
+        Ok(postgres_client::find_imports(&self.pool, limit).await?)
+    }
+}
+
+#[async_trait]
+impl GuideWriter for PostgresRepository {
+    async fn find_source_states(&self) -> Result<Vec<SourceState>, RepositoryError> {
+        Ok(postgres_client::find_source_states(&self.pool).await?)
+    }
+
+    async fn clear_staging(&self) -> Result<(), RepositoryError> {
+        Ok(postgres_client::clear_staging(&self.pool).await?)
+    }
+
+    async fn stage_channels(&self, channels: &[Channel]) -> Result<(), RepositoryError> {
+        Ok(postgres_client::stage_channels(&self.pool, channels).await?)
+    }
+
+    async fn stage_channel_packages(
+        &self,
+        channels: &[Channel],
+        package: &str,
+    ) -> Result<(), RepositoryError> {
+        Ok(postgres_client::stage_channel_packages(&self.pool, channels, package).await?)
+    }
+
+    async fn stage_programs(&self, programs: &[Program]) -> Result<(), RepositoryError> {
+        Ok(postgres_client::bulk_stage_programs(&self.pool, programs).await?)
+    }
+
+    async fn discard_staged_package(
+        &self,
+        package: &str,
+        channel_ids: &[String],
+    ) -> Result<(), RepositoryError> {
+        Ok(postgres_client::discard_staged_package(&self.pool, package, channel_ids).await?)
+    }
+
+    async fn publish_staging(
+        &self,
+        source_states: &[SourceState],
+    ) -> Result<GuideChanges, RepositoryError> {
+        Ok(postgres_client::publish_staging(&self.pool, source_states).await?)
+    }
+}
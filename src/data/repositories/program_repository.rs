@@ -1,20 +1,55 @@
-use crate::data::sources::db::postgres_client;
+use crate::config::prime_time::PrimeTime;
+use crate::domain::entities::page::{PageRequest, Paged};
 use crate::domain::entities::program::Program;
-use crate::presentation::dtos::Page;
+use crate::domain::entities::time_window::TimeWindow;
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::ProgramRepository;
+use chrono::NaiveDate;
 
-pub fn get_programs_by_channel_id(channel_id: String) -> Page<Program> {
-    let programs = postgres_client::find_programs_by_channel_id(&channel_id);
-    Page { content: programs }
+pub async fn get_programs_by_channel_id(
+    repository: &dyn ProgramRepository,
+    channel_id: String,
+    window: TimeWindow,
+    page: PageRequest,
+) -> Result<Paged<Program>, RepositoryError> {
+    repository
+        .find_programs_by_channel_id(&channel_id, window, page)
+        .await
 }
 
-pub(crate) fn get_current_program_by_channel_id(channel_id: String) -> Program {
-    postgres_client::find_current_program_by_channel_id(&channel_id)
+pub(crate) async fn get_current_program_by_channel_id(
+    repository: &dyn ProgramRepository,
+    channel_id: String,
+) -> Result<Option<Program>, RepositoryError> {
+    repository
+        .find_current_program_by_channel_id(&channel_id)
+        .await
 }
 
-pub(crate) fn get_tonight_program_by_channel_id(channel_id: String) -> Program {
-    postgres_client::find_tonight_program_by_channel_id(&channel_id)
+///
+/// The first `parts` programmes of the evening of `date` on a channel
+///
+pub(crate) async fn get_tonight_programs_by_channel_id(
+    repository: &dyn ProgramRepository,
+    channel_id: String,
+    prime_time: &PrimeTime,
+    date: NaiveDate,
+    parts: u32,
+) -> Result<Vec<Program>, RepositoryError> {
+    repository
+        .find_tonight_programs_by_channel_id(
+            &channel_id,
+            prime_time.start_on(date),
+            prime_time.min_duration,
+            parts,
+        )
+        .await
 }
 
-pub(crate) fn search_programs(query: String) -> Vec<Program> {
-    postgres_client::search_programs(query)
+pub(crate) async fn search_programs(
+    repository: &dyn ProgramRepository,
+    query: String,
+    page: PageRequest,
+) -> Result<Paged<Program>, RepositoryError> {
+    repository.search_programs(&query, page).await
 }
@@ -0,0 +1,203 @@
+use crate::data::sources::sqlite::database::SqliteDatabase;
+use crate::data::sources::sqlite::sqlite_client;
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::import_report::{GuideChanges, ImportReport};
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::entities::program::Program;
+use crate::domain::entities::source_state::SourceState;
+use crate::domain::entities::time_window::TimeWindow;
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::{
+    ChannelRepository, GuideWriter, ImportRepository, ProgramRepository,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+
+///
+/// The guide stored in a SQLite file
+///
+pub struct SqliteRepository {
+    database: SqliteDatabase,
+}
+
+impl SqliteRepository {
+    pub fn new(database: SqliteDatabase) -> SqliteRepository {
+        SqliteRepository { database }
+    }
+}
+
+#[async_trait]
+impl ChannelRepository for SqliteRepository {
+    async fn find_all_channels(
+        &self,
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        Ok(sqlite_client::find_all_channels(&self.database, page).await?)
+    }
+
+    async fn find_channels_by_package(
+        &self,
+        package: &str,
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        Ok(
+            sqlite_client::find_channels_by_package(&self.database, package.to_string(), page)
+                .await?,
+        )
+    }
+
+    async fn find_channels_by_ids(
+        &self,
+        channel_ids: &[String],
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError> {
+        Ok(sqlite_client::find_channels_by_ids(&self.database, channel_ids.to_vec(), page).await?)
+    }
+}
+
+#[async_trait]
+impl ProgramRepository for SqliteRepository {
+    async fn find_programs_by_channel_id(
+        &self,
+        channel_id: &str,
+        window: TimeWindow,
+        page: PageRequest,
+    ) -> Result<Paged<Program>, RepositoryError> {
+        Ok(sqlite_client::find_programs_by_channel_id(
+            &self.database,
+            channel_id.to_string(),
+            window,
+            page,
+        )
+        .await?)
+    }
+
+    async fn find_programs_by_channel_ids(
+        &self,
+        channel_ids: &[String],
+        window: TimeWindow,
+    ) -> Result<Vec<Program>, RepositoryError> {
+        Ok(sqlite_client::find_programs_by_channel_ids(
+            &self.database,
+            channel_ids.to_vec(),
+            window,
+        )
+        .await?)
+    }
+
+    async fn find_channels_with_upcoming_programs(
+        &self,
+        package: Option<&str>,
+        at: DateTime<Utc>,
+        count: u32,
+        page: PageRequest,
+    ) -> Result<Paged<(Channel, Vec<Program>)>, RepositoryError> {
+        Ok(sqlite_client::find_channels_with_upcoming_programs(
+            &self.database,
+            package.map(str::to_string),
+            at,
+            count,
+            page,
+        )
+        .await?)
+    }
+
+    async fn find_current_program_by_channel_id(
+        &self,
+        channel_id: &str,
+    ) -> Result<Option<Program>, RepositoryError> {
+        Ok(sqlite_client::find_current_program_by_channel_id(
+            &self.database,
+            channel_id.to_string(),
+        )
+        .await?)
+    }
+
+    async fn find_tonight_programs_by_channel_id(
+        &self,
+        channel_id: &str,
+        from: DateTime<Utc>,
+        min_duration: TimeDelta,
+        count: u32,
+    ) -> Result<Vec<Program>, RepositoryError> {
+        Ok(sqlite_client::find_tonight_programs_by_channel_id(
+            &self.database,
+            channel_id.to_string(),
+            from,
+            min_duration,
+            count,
+        )
+        .await?)
+    }
+
+    async fn search_programs(
+        &self,
+        query: &str,
+        page: PageRequest,
+    ) -> Result<Paged<Program>, RepositoryError> {
+        Ok(sqlite_client::search_programs(&self.database, query.to_string(), page).await?)
+    }
+}
+
+#[async_trait]
+impl ImportRepository for SqliteRepository {
+    async fn save_import(&self, report: &ImportReport) -> Result<i32, RepositoryError> {
+        Ok(sqlite_client::save_import(&self.database, report.clone()).await?)
+    }
+
+    async fn find_imports(&self, limit: i64) -> Result<Vec<ImportReport>, RepositoryError> {
+        Ok(sqlite_client::find_imports(&self.database, limit).await?)
+    }
+}
+
+#[async_trait]
+impl GuideWriter for SqliteRepository {
+    async fn find_source_states(&self) -> Result<Vec<SourceState>, RepositoryError> {
+        Ok(sqlite_client::find_source_states(&self.database).await?)
+    }
+
+    async fn clear_staging(&self) -> Result<(), RepositoryError> {
+        Ok(sqlite_client::clear_staging(&self.database).await?)
+    }
+
+    async fn stage_channels(&self, channels: &[Channel]) -> Result<(), RepositoryError> {
+        Ok(sqlite_client::stage_channels(&self.database, channels.to_vec()).await?)
+    }
+
+    async fn stage_channel_packages(
+        &self,
+        channels: &[Channel],
+        package: &str,
+    ) -> Result<(), RepositoryError> {
+        Ok(sqlite_client::stage_channel_packages(
+            &self.database,
+            channels.to_vec(),
+            package.to_string(),
+        )
+        .await?)
+    }
+
+    async fn stage_programs(&self, programs: &[Program]) -> Result<(), RepositoryError> {
+        Ok(sqlite_client::stage_programs(&self.database, programs.to_vec()).await?)
+    }
+
+    async fn discard_staged_package(
+        &self,
+        package: &str,
+        channel_ids: &[String],
+    ) -> Result<(), RepositoryError> {
+        Ok(sqlite_client::discard_staged_package(
+            &self.database,
+            package.to_string(),
+            channel_ids.to_vec(),
+        )
+        .await?)
+    }
+
+    async fn publish_staging(
+        &self,
+        source_states: &[SourceState],
+    ) -> Result<GuideChanges, RepositoryError> {
+        Ok(sqlite_client::publish_staging(&self.database, source_states.to_vec()).await?)
+    }
+}
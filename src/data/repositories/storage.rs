@@ -0,0 +1,106 @@
+use crate::data::repositories::memory_repository::MemoryRepository;
+use crate::data::repositories::postgres_repository::PostgresRepository;
+use crate::data::repositories::sqlite_repository::SqliteRepository;
+use crate::data::sources::db::pool::DbPool;
+use crate::data::sources::sqlite::database::SqliteDatabase;
+use crate::domain::repositories::{
+    ChannelRepository, GuideWriter, ImportRepository, ProgramRepository,
+};
+use dotenv::var;
+use std::sync::Arc;
+
+///
+/// Where the guide is stored
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageBackend {
+    Postgres,
+    /// A single file, see `SQLITE_PATH`
+    Sqlite,
+    /// Nothing is persisted, the guide is imported again on restart
+    Memory,
+}
+
+impl StorageBackend {
+    ///
+    /// Read the backend from the `STORAGE_BACKEND` environment variable,
+    /// PostgreSQL being the default
+    ///
+    pub fn from_env() -> StorageBackend {
+        match var("STORAGE_BACKEND") {
+            Ok(value) => StorageBackend::parse(&value).unwrap_or_else(|| {
+                eprintln!("Invalid STORAGE_BACKEND value {}", value);
+                StorageBackend::Postgres
+            }),
+            Err(_) => StorageBackend::Postgres,
+        }
+    }
+
+    fn parse(value: &str) -> Option<StorageBackend> {
+        match value.trim().to_lowercase().as_str() {
+            "postgres" | "postgresql" => Some(StorageBackend::Postgres),
+            "sqlite" => Some(StorageBackend::Sqlite),
+            "memory" => Some(StorageBackend::Memory),
+            _ => None,
+        }
+    }
+}
+
+///
+/// The repositories of the selected backend
+///
+#[derive(Clone)]
+pub struct Storage {
+    pub channels: Arc<dyn ChannelRepository>,
+    pub programs: Arc<dyn ProgramRepository>,
+    pub imports: Arc<dyn ImportRepository>,
+    pub guide: Arc<dyn GuideWriter>,
+}
+
+impl Storage {
+    pub fn postgres(pool: DbPool) -> Storage {
+        Storage::from_repository(Arc::new(PostgresRepository::new(pool)))
+    }
+
+    pub fn sqlite(database: SqliteDatabase) -> Storage {
+        Storage::from_repository(Arc::new(SqliteRepository::new(database)))
+    }
+
+    pub fn memory() -> Storage {
+        Storage::from_repository(Arc::new(MemoryRepository::default()))
+    }
+
+    fn from_repository<R>(repository: Arc<R>) -> Storage
+    where
+        R: ChannelRepository + ProgramRepository + ImportRepository + GuideWriter + 'static,
+    {
+        Storage {
+            channels: repository.clone(),
+            programs: repository.clone(),
+            imports: repository.clone(),
+            guide: repository,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!(
+            StorageBackend::parse("memory"),
+            Some(StorageBackend::Memory)
+        );
+        assert_eq!(
+            StorageBackend::parse(" Postgres "),
+            Some(StorageBackend::Postgres)
+        );
+        assert_eq!(
+            StorageBackend::parse("sqlite"),
+            Some(StorageBackend::Sqlite)
+        );
+        assert_eq!(StorageBackend::parse("mongodb"), None);
+    }
+}
@@ -1,123 +1,544 @@
+use crate::config::packages::{self, PackageConfig, PackageMode};
 use crate::data::converters::{channel_converter, program_converter};
-use crate::data::sources::api as xmltv_client;
-use crate::data::sources::db::postgres_client;
+use crate::data::errors::IngestionError;
+use crate::data::models::{Channel as ChannelModel, Program as ProgramModel};
+use crate::data::repositories::storage::Storage;
+use crate::data::sources::xmltv::parser::XmlTvItem;
+use crate::data::sources::xmltv::{self, XmlTvFetch, XmlTvPayload, XmlTvSource};
 use crate::domain::entities::channel::Channel;
+use crate::domain::entities::import_report::{ImportReport, PackageReport};
 use crate::domain::entities::program::Program;
+use crate::domain::entities::source_state::SourceState;
+use crate::domain::errors::RepositoryError;
+use crate::domain::repositories::GuideWriter;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
-pub async fn init_xml_tv_data() {
-    // Initialize the database connection or any other setup if needed
+/// Number of channels or programmes kept in memory before being staged
+const BATCH_SIZE: usize = 10000;
+
+/// Number of batches parsed ahead of the database writes
+const STAGING_QUEUE_SIZE: usize = 2;
+
+///
+/// Import the configured packages and publish them as the new guide.
+/// Malformed channels and programmes are skipped and counted in the report.
+/// `progress` is notified each time the import moves to a new step.
+///
+pub async fn init_xml_tv_data(
+    storage: Storage,
+    progress: impl Fn(&str) + Send + Sync,
+) -> ImportReport {
     println!("Initializing xml tv data...");
+    let mut report = ImportReport::new();
+
+    if let Err(e) = import(&storage, &mut report, &progress).await {
+        eprintln!("Failed to initialize XML TV data: {}", e);
+        report.error = Some(e.to_string());
+    }
+
+    report.finish();
+    println!(
+        "Time taken to init database: {}ms ({} parsed, {} skipped, {} inserted)",
+        report.duration_ms, report.parsed, report.skipped, report.inserted
+    );
 
-    let handle = std::thread::spawn(move || async {
-        let start_time = std::time::Instant::now();
-        println!("Fetching XML TV data from...");
-        let result = xmltv_client::fetch_xmltv_all().await;
-        let result = match result {
-            Ok(res) => res,
+    match storage.imports.save_import(&report).await {
+        Ok(id) => report.id = id,
+        Err(e) => eprintln!("Unable to save the import report: {}", e),
+    }
+    report
+}
+
+async fn import(
+    storage: &Storage,
+    report: &mut ImportReport,
+    progress: &(dyn Fn(&str) + Send + Sync),
+) -> Result<(), IngestionError> {
+    let config = packages::load().map_err(IngestionError::Config)?;
+    let previous_states = storage
+        .guide
+        .find_source_states()
+        .await?
+        .into_iter()
+        .map(|state| (state.source.clone(), state))
+        .collect::<HashMap<String, SourceState>>();
+
+    // Every source is fetched first, the guide is only rewritten when one of them changed
+    let mut fetches = vec![];
+    let mut sources = HashSet::new();
+    let mut changed = false;
+    for package_config in &config.packages {
+        progress(&format!("Fetching package {}", package_config.id));
+        let source = match package_config.xmltv_source() {
+            Ok(source) => source,
             Err(e) => {
-                panic!("Failed to fetch XML TV data: {}", e);
+                let package = PackageReport::new(&package_config.id, package_config.source.clone());
+                record_package(report, package, &Err(e.clone()));
+                return Err(e);
             }
         };
-        println!("Found {} channels", result.channels.len());
-        println!("Found {} programs", result.programs.len());
-
-        // Drop existing channels before inserting new ones
-        postgres_client::drop_channels();
-        println!("Existing channels dropped from the database.");
-
-        // Drop existing programs before inserting new ones
-        postgres_client::drop_programs();
-        println!("Existing programs dropped from the database.");
-
-        let channels: Vec<Channel> = channel_converter::models_to_entities(result.channels);
-        let programs: Vec<Program> = program_converter::models_to_entities(result.programs);
-
-        postgres_client::save_channels(channels.clone());
-        postgres_client::save_channel_packages(channels.clone(), "ALL".to_string());
-        println!("Channels saved to the database.");
-
-        let mut inserted_programs = 0;
-        let program_chunks = programs.chunks(10000);
-        for chunk in program_chunks {
-            postgres_client::bulk_insert_programs(chunk.to_vec());
-            inserted_programs += chunk.len();
-            println!(
-                "Inserted {} programs of {} into the database.",
-                inserted_programs,
-                programs.len()
-            );
+        let previous = previous_states.get(&source.to_string());
+        println!("Fetching XML TV data from {}...", source);
+        let fetch = source
+            .fetch_with_mirrors(&package_config.xmltv_mirrors(), previous)
+            .await;
+        match &fetch {
+            Ok(fetch) => changed |= fetch.changed(previous),
+            Err(e) if package_config.required => {
+                let package = PackageReport::new(&package_config.id, source.to_string());
+                record_package(report, package, &Err(e.clone()));
+                return Err(e.clone());
+            }
+            Err(_) => {}
         }
-        println!("Programs saved to the database.");
+        sources.insert(source.to_string());
+        fetches.push((package_config, source, fetch));
+    }
 
-        let existing_channel_ids = channels
-            .into_iter()
-            .map(|c| c.channel_id.clone())
-            .collect::<Vec<String>>();
+    // Packages added to or removed from the configuration change the guide as well
+    if !changed && previous_states.keys().cloned().collect::<HashSet<String>>() == sources {
+        println!("XML TV sources did not change, keeping the current guide.");
+        report.not_modified = true;
+        for (package_config, source, fetch) in fetches {
+            let mut package = PackageReport::new(&package_config.id, source.to_string());
+            package.not_modified = fetch.is_ok();
+            record_package(report, package, &fetch.map(|_| ()));
+        }
+        return Ok(());
+    }
 
-        let fr = xmltv_client::fetch_xmltv_fr().await;
-        let fr = match fr {
-            Ok(res) => res,
-            Err(e) => {
-                panic!("Failed to fetch FR XML TV data: {}", e);
+    // The new guide is loaded into staging tables and only replaces
+    // the live one once everything has been imported
+    storage.guide.clear_staging().await?;
+
+    // Channels imported so far, a channel belongs to the first package declaring it
+    let mut known_channel_ids = HashSet::new();
+    let mut source_states = vec![];
+    for (package_config, source, fetch) in fetches {
+        progress(&format!("Importing package {}", package_config.id));
+        let fetch = match fetch {
+            // The unchanged sources are needed as well to rebuild the guide
+            Ok(XmlTvFetch::NotModified) => {
+                source
+                    .fetch_with_mirrors(&package_config.xmltv_mirrors(), None)
+                    .await
             }
+            fetch => fetch,
         };
-        let fr_channels: Vec<Channel> = channel_converter::models_to_entities(fr.channels);
-        println!("Found {} FR channels", fr_channels.len());
-        let known_fr_channels = fr_channels
-            .iter()
-            .filter(|c| existing_channel_ids.contains(&c.channel_id))
-            .cloned()
-            .collect::<Vec<Channel>>();
-        println!("Known FR channels: {}", known_fr_channels.len());
-        postgres_client::save_channel_packages(known_fr_channels, "FR".to_string());
-        let unknown_fr_channels = fr_channels
-            .iter()
-            .filter(|c| !existing_channel_ids.contains(&c.channel_id))
-            .cloned()
-            .collect::<Vec<Channel>>();
-        println!("Unknown FR channels: {}", unknown_fr_channels.len());
-        postgres_client::save_channels(unknown_fr_channels.clone());
-        postgres_client::save_channel_packages(unknown_fr_channels, "FR".to_string());
-        let unknown_fr_programs = fr
-            .programs
-            .into_iter()
-            .filter(|p| !existing_channel_ids.contains(&p.channel))
-            .map(|p| program_converter::model_to_entity(p))
-            .collect::<Vec<Program>>();
-        if unknown_fr_programs.len() > 0 {
-            println!("Found {} unknown FR channels", unknown_fr_programs.len());
-            postgres_client::bulk_insert_programs(unknown_fr_programs);
-        } else {
-            println!("No unknown FR channels found");
+        let (package, result) = match fetch {
+            Ok(XmlTvFetch::Fetched { payload, state }) => {
+                let (import, result) = import_package(
+                    storage,
+                    package_config,
+                    &source,
+                    payload,
+                    mem::take(report),
+                    known_channel_ids,
+                )
+                .await;
+                *report = import.report;
+                known_channel_ids = import.known_channel_ids;
+                let result = match result {
+                    Ok(()) => {
+                        known_channel_ids.extend(import.new_channel_ids);
+                        source_states.push(state);
+                        Ok(())
+                    }
+                    // Do not publish half of a package
+                    Err(e) => storage
+                        .guide
+                        .discard_staged_package(
+                            &package_config.id,
+                            &import.new_channel_ids.into_iter().collect::<Vec<String>>(),
+                        )
+                        .await
+                        .map_err(IngestionError::from)
+                        .and(Err(e)),
+                };
+                (import.package, result)
+            }
+            Ok(XmlTvFetch::NotModified) => (
+                PackageReport::new(&package_config.id, source.to_string()),
+                Err(IngestionError::Fetch(
+                    "The source could not be downloaded again".to_string(),
+                )),
+            ),
+            Err(e) => (
+                PackageReport::new(&package_config.id, source.to_string()),
+                Err(e),
+            ),
+        };
+        record_package(report, package, &result);
+        match result {
+            Err(e) if package_config.required => return Err(e),
+            Err(e @ IngestionError::Database(_)) => return Err(e),
+            _ => {}
         }
-        println!("FR channels saved to the database.");
+    }
 
-        let tnt = match xmltv_client::fetch_xmltv_tnt().await {
-            Ok(res) => res,
-            Err(e) => {
-                panic!("Failed to fetch TNT XML TV data: {}", e);
+    if known_channel_ids.is_empty() {
+        return Err(IngestionError::Parse(
+            "No channel could be imported".to_string(),
+        ));
+    }
+
+    progress("Publishing the new guide");
+    report.changes = storage.guide.publish_staging(&source_states).await?;
+    Ok(())
+}
+
+fn record_package(
+    report: &mut ImportReport,
+    mut package: PackageReport,
+    result: &Result<(), IngestionError>,
+) {
+    if let Err(e) = result {
+        eprintln!("Failed to import package {}: {}", package.package, e);
+        report.warn(format!("[{}] {}", package.package, e));
+        package.error = Some(e.to_string());
+    }
+    report.packages.push(package);
+}
+
+///
+/// Stream the content of a package to the staging tables
+///
+async fn import_package(
+    storage: &Storage,
+    config: &PackageConfig,
+    source: &XmlTvSource,
+    payload: XmlTvPayload,
+    report: ImportReport,
+    known_channel_ids: HashSet<String>,
+) -> (PackageImport, Result<(), IngestionError>) {
+    let (batches, queue) = mpsc::channel(STAGING_QUEUE_SIZE);
+    let writer = tokio::spawn(stage_batches(
+        storage.guide.clone(),
+        config.id.clone(),
+        queue,
+    ));
+    let import = PackageImport::new(config, source, report, known_channel_ids, Some(batches));
+
+    // Parsing is blocking, keep it off the async workers while the batches are written.
+    // The import is shared with the reading task so that it survives a panic.
+    let shared = Arc::new(Mutex::new(import));
+    let reader = shared.clone();
+    let entry = config.entry.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut import = reader.lock().unwrap_or_else(PoisonError::into_inner);
+        import.read(&payload, entry.as_deref())
+    })
+    .await
+    .unwrap_or_else(|e| Err(IngestionError::Task(e.to_string())));
+    // The reading task is over, whether it succeeded, panicked or never ran:
+    // take the import back, leaving an empty one behind
+    let empty = PackageImport::new(
+        config,
+        source,
+        ImportReport::default(),
+        HashSet::new(),
+        None,
+    );
+    let mut import = mem::replace(
+        &mut *shared.lock().unwrap_or_else(PoisonError::into_inner),
+        empty,
+    );
+    // Let the writer know that no batch is coming anymore
+    import.batches = None;
+    match writer.await {
+        Ok(Ok(elapsed)) => {
+            import.package.add_staging_time(elapsed);
+            (import, result)
+        }
+        // The writer stopping is the cause of the reading failure
+        Ok(Err(e)) => (import, Err(e.into())),
+        Err(e) => (import, Err(IngestionError::Task(e.to_string()))),
+    }
+}
+
+///
+/// Rows to write to the staging tables
+///
+enum StagingBatch {
+    /// Channels already staged, only joining the package
+    Members(Vec<Channel>),
+    /// Channels first declared by the package
+    Channels(Vec<Channel>),
+    Programs(Vec<Program>),
+}
+
+///
+/// Write the batches of a package to the staging tables as they come,
+/// returning the time spent writing
+///
+async fn stage_batches(
+    guide: Arc<dyn GuideWriter>,
+    package: String,
+    mut queue: mpsc::Receiver<StagingBatch>,
+) -> Result<Duration, RepositoryError> {
+    let mut elapsed = Duration::ZERO;
+    while let Some(batch) = queue.recv().await {
+        let started_at = Instant::now();
+        match batch {
+            StagingBatch::Members(channels) => {
+                guide.stage_channel_packages(&channels, &package).await?
+            }
+            StagingBatch::Channels(channels) => {
+                guide.stage_channels(&channels).await?;
+                guide.stage_channel_packages(&channels, &package).await?
             }
+            StagingBatch::Programs(programs) => guide.stage_programs(&programs).await?,
+        }
+        elapsed += started_at.elapsed();
+    }
+    Ok(elapsed)
+}
+
+///
+/// The state of a package import, fed one element at a time
+///
+struct PackageImport {
+    /// Queue of the batches to stage, closed once the package is read
+    batches: Option<mpsc::Sender<StagingBatch>>,
+    mode: PackageMode,
+    report: ImportReport,
+    package: PackageReport,
+    /// Channels imported by the previous packages
+    known_channel_ids: HashSet<String>,
+    /// Channels first declared by this package
+    new_channel_ids: HashSet<String>,
+    /// Channels waiting to be staged
+    channels: Vec<ChannelModel>,
+    /// Programmes waiting to be staged
+    programs: Vec<ProgramModel>,
+    /// Programmes skipped because their channel is not declared
+    orphans: usize,
+}
+
+impl PackageImport {
+    fn new(
+        config: &PackageConfig,
+        source: &XmlTvSource,
+        report: ImportReport,
+        known_channel_ids: HashSet<String>,
+        batches: Option<mpsc::Sender<StagingBatch>>,
+    ) -> PackageImport {
+        PackageImport {
+            batches,
+            mode: config.mode,
+            report,
+            package: PackageReport::new(&config.id, source.to_string()),
+            known_channel_ids,
+            new_channel_ids: HashSet::new(),
+            channels: vec![],
+            programs: vec![],
+            orphans: 0,
+        }
+    }
+
+    fn read(&mut self, payload: &XmlTvPayload, entry: Option<&str>) -> Result<(), IngestionError> {
+        xmltv::read(payload, entry, &mut |item| self.on_item(item))?;
+        self.flush_channels()?;
+        self.flush_programs()?;
+        if self.orphans > 0 {
+            self.warn(format!(
+                "Skipped {} programmes of undeclared channels",
+                self.orphans
+            ));
+        }
+        println!(
+            "{}: {} channels and {} programs saved to the database, {} channels joined.",
+            self.package.package,
+            self.package.channels_inserted,
+            self.package.programs_inserted,
+            self.package.channels_joined
+        );
+        Ok(())
+    }
+
+    fn on_item(
+        &mut self,
+        item: Result<XmlTvItem, IngestionError>,
+    ) -> Result<ControlFlow<()>, IngestionError> {
+        match item {
+            Ok(XmlTvItem::Channel(channel)) => {
+                self.channels.push(channel);
+                if self.channels.len() >= BATCH_SIZE {
+                    self.flush_channels()?;
+                }
+            }
+            Ok(XmlTvItem::Program(program)) => {
+                // Channels are declared before the programmes
+                self.flush_channels()?;
+                if self.mode == PackageMode::Channels {
+                    return Ok(ControlFlow::Break(()));
+                }
+                // Programmes of the channels imported by previous packages are already known
+                if self.known_channel_ids.contains(&program.channel) {
+                    return Ok(ControlFlow::Continue(()));
+                }
+                self.programs.push(*program);
+                if self.programs.len() >= BATCH_SIZE {
+                    self.flush_programs()?;
+                }
+            }
+            Err(e @ IngestionError::InvalidChannel { .. }) => {
+                self.package.channels_parsed += 1;
+                self.package.channels_skipped += 1;
+                self.warn(e.to_string());
+            }
+            Err(IngestionError::InvalidProgram { channel_id, .. })
+                if self.mode == PackageMode::Channels
+                    || self.known_channel_ids.contains(&channel_id) => {}
+            Err(e @ IngestionError::InvalidProgram { .. }) => {
+                self.package.programs_parsed += 1;
+                self.package.programs_skipped += 1;
+                self.warn(e.to_string());
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    ///
+    /// Hand a batch to the staging writer, waiting while it is busy with the previous ones
+    ///
+    fn stage(&self, batch: StagingBatch) -> Result<(), IngestionError> {
+        self.batches
+            .as_ref()
+            .and_then(|batches| batches.blocking_send(batch).ok())
+            .ok_or_else(|| IngestionError::Database("The staging writer stopped".to_string()))
+    }
+
+    fn warn(&mut self, warning: String) {
+        self.report
+            .warn(format!("[{}] {}", self.package.package, warning));
+    }
+
+    ///
+    /// Stage the package membership, along with the channels
+    /// which were not imported by the previous packages
+    ///
+    fn flush_channels(&mut self) -> Result<(), IngestionError> {
+        if self.channels.is_empty() {
+            return Ok(());
+        }
+        let models = mem::take(&mut self.channels);
+        self.package.channels_parsed += models.len();
+        let (channels, errors) = channel_converter::models_to_entities(models);
+        self.package.channels_skipped += errors.len();
+        for e in errors {
+            self.warn(e.to_string());
+        }
+        if self.mode == PackageMode::Channels {
+            let known_channels = channels
+                .into_iter()
+                .filter(|c| self.known_channel_ids.contains(&c.channel_id))
+                .collect::<Vec<Channel>>();
+            self.package.channels_joined += known_channels.len();
+            return self.stage(StagingBatch::Members(known_channels));
+        }
+
+        let (known_channels, new_channels): (Vec<Channel>, Vec<Channel>) = channels
+            .into_iter()
+            .filter(|c| !self.new_channel_ids.contains(&c.channel_id))
+            .partition(|c| self.known_channel_ids.contains(&c.channel_id));
+        self.package.channels_joined += known_channels.len();
+        self.package.channels_inserted += new_channels.len();
+        self.new_channel_ids
+            .extend(new_channels.iter().map(|c| c.channel_id.clone()));
+        self.stage(StagingBatch::Members(known_channels))?;
+        self.stage(StagingBatch::Channels(new_channels))
+    }
+
+    ///
+    /// Stage the pending programmes, skipping the malformed ones and those
+    /// referencing a channel which is not part of the guide
+    ///
+    fn flush_programs(&mut self) -> Result<(), IngestionError> {
+        if self.programs.is_empty() {
+            return Ok(());
+        }
+        let models = mem::take(&mut self.programs);
+        self.package.programs_parsed += models.len();
+        let (programs, errors) = program_converter::models_to_entities(models);
+        self.package.programs_skipped += errors.len();
+        for e in errors {
+            self.warn(e.to_string());
+        }
+
+        let (programs, orphans): (Vec<Program>, Vec<Program>) = programs
+            .into_iter()
+            .partition(|p| self.new_channel_ids.contains(&p.channel_id));
+        self.package.programs_skipped += orphans.len();
+        self.orphans += orphans.len();
+
+        let count = programs.len();
+        self.stage(StagingBatch::Programs(programs))?;
+        self.package.programs_inserted += count;
+        println!(
+            "Inserted {} programs of {} into the database.",
+            self.package.programs_inserted, self.package.package
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::models::DisplayName;
+
+    fn channel_model(id: &str) -> ChannelModel {
+        ChannelModel {
+            id: id.to_string(),
+            display_name: DisplayName {
+                content: id.to_string(),
+            },
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn test_flush_channels_of_a_channel_only_package() {
+        // GIVEN
+        let config = PackageConfig {
+            mode: PackageMode::Channels,
+            ..packages::PackagesConfig::default().packages[0].clone()
         };
-        let tnt_channels: Vec<Channel> = channel_converter::models_to_entities(tnt.channels);
-        println!("Found {} TNT channels", tnt_channels.len());
-        let known_tnt_channels = tnt_channels
-            .iter()
-            .filter(|c| existing_channel_ids.contains(&c.channel_id))
-            .cloned()
-            .collect::<Vec<Channel>>();
-        println!("Known TNT channels: {}", known_tnt_channels.len());
-        postgres_client::save_channel_packages(known_tnt_channels, "TNT".to_string());
-        println!("TNT channels saved to the database.");
-
-        let elapsed = start_time.elapsed();
-        println!("Time taken to init database: {:.2?}", elapsed);
-    })
-    .join();
+        let (batches, mut queue) = mpsc::channel(STAGING_QUEUE_SIZE);
+        let mut import = PackageImport::new(
+            &config,
+            &XmlTvSource::File("guide.xml".into()),
+            ImportReport::default(),
+            HashSet::from(["TF1.fr".to_string()]),
+            Some(batches),
+        );
+        import.channels = vec![
+            channel_model("TF1.fr"),
+            channel_model("Unknown.fr"),
+            channel_model(" "),
+        ];
+
+        // WHEN
+        import.flush_channels().unwrap();
 
-    if let Ok(handle) = handle {
-        handle.await;
-        println!("XML TV data initialization complete.");
-    } else {
-        eprintln!("Failed to initialize XML TV data.");
+        // THEN
+        assert_eq!(import.package.channels_parsed, 3);
+        assert_eq!(import.package.channels_skipped, 1);
+        assert_eq!(import.package.channels_joined, 1);
+        assert_eq!(import.report.warnings.len(), 1);
+        match queue.try_recv() {
+            Ok(StagingBatch::Members(channels)) => {
+                assert_eq!(channels.len(), 1);
+                assert_eq!(channels[0].channel_id, "TF1.fr");
+            }
+            _ => panic!("The known channels should join the package"),
+        }
     }
 }
@@ -1,91 +1,223 @@
-use crate::data::models::XmlTv;
-use dotenv::var;
-use std::io::{Cursor, Read};
-use zip::ZipArchive;
-
-pub fn base_url() -> String {
-    let base_url =
-        var("XMLTV_BASE_URL").expect("XMLTV_BASE_URL must be set in the environment variables");
-    if base_url.ends_with("/") {
-        return base_url;
-    }
-    return base_url + "/";
-}
+use crate::config::env::env_u64;
+use crate::data::errors::IngestionError;
+use crate::data::sources::file;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::NamedTempFile;
 
-pub fn xmltv_url_all() -> String {
-    format!("{}xmltv.zip", base_url())
+///
+/// A XMLTV payload saved to a temporary file
+///
+pub struct Download {
+    pub file: NamedTempFile,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// The SHA-256 of the payload, hex encoded
+    pub sha256: String,
 }
 
-pub fn xmltv_url_tnt() -> String {
-    format!("{}xmltv_tnt.zip", base_url())
+///
+/// Limits applied to the XMLTV downloads
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct HttpSettings {
+    pub connect_timeout: Duration,
+    /// Maximum time without receiving any data
+    pub read_timeout: Duration,
+    /// Number of retries after a failed attempt
+    pub retries: u32,
+    /// Delay before the first retry, doubled on each retry
+    pub retry_delay: Duration,
+    pub max_payload_bytes: u64,
 }
 
-pub fn xmltv_url_fr() -> String {
-    format!("{}xmltv_fr.zip", base_url())
-}
+impl HttpSettings {
+    ///
+    /// Read the settings from the `XMLTV_CONNECT_TIMEOUT_SECONDS`, `XMLTV_READ_TIMEOUT_SECONDS`,
+    /// `XMLTV_RETRIES`, `XMLTV_RETRY_DELAY_MS` and `XMLTV_MAX_PAYLOAD_BYTES` environment variables
+    ///
+    pub fn from_env() -> HttpSettings {
+        HttpSettings {
+            connect_timeout: Duration::from_secs(env_u64("XMLTV_CONNECT_TIMEOUT_SECONDS", 10)),
+            read_timeout: Duration::from_secs(env_u64("XMLTV_READ_TIMEOUT_SECONDS", 60)),
+            retries: env_u64("XMLTV_RETRIES", 3) as u32,
+            retry_delay: Duration::from_millis(env_u64("XMLTV_RETRY_DELAY_MS", 1000)),
+            max_payload_bytes: env_u64("XMLTV_MAX_PAYLOAD_BYTES", 1024 * 1024 * 1024),
+        }
+    }
 
-pub async fn fetch_xmltv_all() -> Result<XmlTv, String> {
-    fetch_xmltv(xmltv_url_all()).await
+    ///
+    /// The delay before the given retry, starting at 0
+    ///
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.retry_delay.saturating_mul(2u32.saturating_pow(retry))
+    }
 }
 
-pub async fn fetch_xmltv_tnt() -> Result<XmlTv, String> {
-    fetch_xmltv(xmltv_url_tnt()).await
+///
+/// Why a download attempt failed
+///
+enum Failure {
+    /// Worth another attempt (timeout, connection reset, server error...)
+    Transient(IngestionError),
+    Permanent(IngestionError),
 }
 
-pub async fn fetch_xmltv_fr() -> Result<XmlTv, String> {
-    fetch_xmltv(xmltv_url_fr()).await
+///
+/// Download the XMLTV payload at the given URL to a temporary file,
+/// one chunk at a time. The request is conditional when `etag` or
+/// `last_modified` are given, `None` is returned if the server answers
+/// that the payload did not change.
+/// Failed attempts are retried with an exponential backoff.
+///
+pub async fn download(
+    request_url: &str,
+    etag: Option<&str>,
+    last_modified: Option<&str>,
+) -> Result<Option<Download>, IngestionError> {
+    let settings = HttpSettings::from_env();
+    let client = Client::builder()
+        .connect_timeout(settings.connect_timeout)
+        .read_timeout(settings.read_timeout)
+        .build()
+        .map_err(|e| IngestionError::Fetch(e.to_string()))?;
+
+    let mut retry = 0;
+    loop {
+        match try_download(&client, &settings, request_url, etag, last_modified).await {
+            Ok(download) => return Ok(download),
+            Err(Failure::Transient(e)) if retry < settings.retries => {
+                let delay = settings.backoff(retry);
+                eprintln!(
+                    "Failed to fetch {}: {}, retrying in {}ms",
+                    request_url,
+                    e,
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            Err(Failure::Transient(e)) | Err(Failure::Permanent(e)) => return Err(e),
+        }
+    }
 }
 
-pub async fn fetch_xmltv(request_url: String) -> Result<XmlTv, String> {
+async fn try_download(
+    client: &Client,
+    settings: &HttpSettings,
+    request_url: &str,
+    etag: Option<&str>,
+    last_modified: Option<&str>,
+) -> Result<Option<Download>, Failure> {
     println!("Fetching XML TV from {}", request_url);
-    let response = reqwest::get(request_url).await.map_err(|e| e.to_string())?;
+    let mut request = client.get(request_url);
+    if let Some(etag) = etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    let mut response = request.send().await.map_err(request_failure)?;
 
     let status = response.status();
     println!("Response status: {}", status);
+    if status == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
     if !status.is_success() {
         eprintln!("Received error response: {}", status);
-        Err(format!("Failed to fetch XMLTV data: {}", status))
-    } else {
-        println!("Extracting XMLTV data from zip file...");
-        let bytes = response.bytes().await.map_err(|e| e.to_string())?;
-        let zip_bytes = Cursor::new(bytes);
-        let mut archive = match ZipArchive::new(zip_bytes) {
-            Ok(arch) => arch,
-            Err(e) => {
-                eprintln!("Failed to read zip archive: {}", e);
-                return Err(format!("Failed to read zip archive: {}", e));
-            }
-        };
+        let e = IngestionError::Fetch(format!("HTTP status {}", status));
+        if status.is_server_error()
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || status == StatusCode::REQUEST_TIMEOUT
+        {
+            return Err(Failure::Transient(e));
+        }
+        return Err(Failure::Permanent(e));
+    }
 
-        for i in 0..archive.len() {
-            let mut file = match archive.by_index(i) {
-                Ok(f) => f,
-                Err(e) => {
-                    eprintln!("Failed to access file in zip archive: {}", e);
-                    return Err(format!("Failed to access file in zip archive: {}", e));
-                }
-            };
-            if file.name().ends_with(".xml") {
-                let mut content = String::new();
-                match file.read_to_string(&mut content) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        eprintln!("Failed to read XML file from zip archive: {}", e);
-                        return Err(format!("Failed to read XML file from zip archive: {}", e));
-                    }
-                }
-                println!("Parsing XML TV data...");
-                let xml_tv: XmlTv = match serde_xml_rs::from_str(&content) {
-                    Ok(data) => data,
-                    Err(e) => {
-                        eprintln!("Failed to parse XML TV data: {}", e);
-                        return Err(format!("Failed to parse XML TV data: {}", e));
-                    }
-                };
-                return Ok(xml_tv);
-            }
+    let too_large = || {
+        Failure::Permanent(IngestionError::Fetch(format!(
+            "Payload larger than {} bytes",
+            settings.max_payload_bytes
+        )))
+    };
+    if response
+        .content_length()
+        .is_some_and(|length| length > settings.max_payload_bytes)
+    {
+        return Err(too_large());
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let content_type = header(CONTENT_TYPE);
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+
+    let io_failure = |e: std::io::Error| Failure::Permanent(IngestionError::Fetch(e.to_string()));
+    let mut file = NamedTempFile::new().map_err(io_failure)?;
+    let mut hasher = Sha256::new();
+    let mut length = 0u64;
+    while let Some(chunk) = response.chunk().await.map_err(request_failure)? {
+        length += chunk.len() as u64;
+        if length > settings.max_payload_bytes {
+            return Err(too_large());
         }
+        hasher.update(&chunk);
+        file.write_all(&chunk).map_err(io_failure)?;
+    }
+    file.flush().map_err(io_failure)?;
+    Ok(Some(Download {
+        file,
+        content_type,
+        etag,
+        last_modified,
+        sha256: file::to_hex(&hasher.finalize()),
+    }))
+}
+
+///
+/// Only timeouts, connection and body errors are worth another attempt,
+/// an invalid URL or request never succeeds
+///
+fn request_failure(e: reqwest::Error) -> Failure {
+    let retryable = e.is_timeout() || e.is_connect() || e.is_body();
+    let e = IngestionError::Fetch(e.to_string());
+    if retryable {
+        Failure::Transient(e)
+    } else {
+        Failure::Permanent(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        // GIVEN
+        let settings = HttpSettings {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(60),
+            retries: 3,
+            retry_delay: Duration::from_millis(500),
+            max_payload_bytes: 1024,
+        };
 
-        Err("No XML file found in the archive.".to_string())
+        // THEN
+        assert_eq!(settings.backoff(0), Duration::from_millis(500));
+        assert_eq!(settings.backoff(1), Duration::from_millis(1000));
+        assert_eq!(settings.backoff(2), Duration::from_millis(2000));
     }
 }
@@ -1,8 +1,3 @@
-pub const SCHEMA_CREATION_QUERY: &'static str = "
-DROP TABLE IF EXISTS PROGRAMS;
-DROP TABLE IF EXISTS CHANNEL_PACKAGES;
-DROP TABLE IF EXISTS CHANNELS;
-
 CREATE TABLE IF NOT EXISTS CHANNELS (
     ID SERIAL PRIMARY KEY ,
     CHANNEL_ID VARCHAR(255) NOT NULL UNIQUE,
@@ -34,4 +29,3 @@ CREATE TABLE IF NOT EXISTS CHANNEL_PACKAGES (
     PACKAGE_ID VARCHAR(255) NOT NULL,
     FOREIGN KEY (CHANNEL_ID) REFERENCES CHANNELS(CHANNEL_ID)
 );
-";
\ No newline at end of file
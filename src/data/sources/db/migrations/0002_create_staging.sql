@@ -0,0 +1,26 @@
+-- Staging tables filled during an import and published in a single transaction
+CREATE TABLE IF NOT EXISTS CHANNELS_STAGING (
+    CHANNEL_ID VARCHAR(255) NOT NULL,
+    DISPLAY_NAME VARCHAR(255) NOT NULL,
+    ICON TEXT DEFAULT NULL
+);
+
+CREATE TABLE IF NOT EXISTS PROGRAMS_STAGING (
+    CHANNEL_ID VARCHAR(255) NOT NULL,
+    START_TIME TIMESTAMP NOT NULL,
+    END_TIME TIMESTAMP NOT NULL,
+    TITLE TEXT NOT NULL,
+    SUBTITLE TEXT DEFAULT NULL,
+    DESCRIPTION TEXT DEFAULT NULL,
+    CATEGORIES VARCHAR(255) DEFAULT NULL,
+    ICON TEXT DEFAULT NULL,
+    EPISODE_NUM VARCHAR(50) DEFAULT NULL,
+    RATING_SYSTEM VARCHAR(50) DEFAULT NULL,
+    RATING_VALUE VARCHAR(50) DEFAULT NULL,
+    RATING_ICON TEXT DEFAULT NULL
+);
+
+CREATE TABLE IF NOT EXISTS CHANNEL_PACKAGES_STAGING (
+    CHANNEL_ID VARCHAR(255) NOT NULL,
+    PACKAGE_ID VARCHAR(255) NOT NULL
+);
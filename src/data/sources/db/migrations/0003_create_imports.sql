@@ -0,0 +1,13 @@
+-- History of the XMLTV imports
+CREATE TABLE IF NOT EXISTS IMPORTS (
+    ID SERIAL PRIMARY KEY,
+    STARTED_AT TIMESTAMPTZ NOT NULL,
+    FINISHED_AT TIMESTAMPTZ DEFAULT NULL,
+    DURATION_MS BIGINT NOT NULL,
+    SUCCESS BOOLEAN NOT NULL,
+    PARSED INTEGER NOT NULL,
+    SKIPPED INTEGER NOT NULL,
+    INSERTED INTEGER NOT NULL,
+    ERROR TEXT DEFAULT NULL,
+    REPORT JSONB NOT NULL
+);
@@ -0,0 +1,8 @@
+-- State of the XMLTV sources of the published guide, used to skip unchanged imports
+CREATE TABLE IF NOT EXISTS SOURCE_STATES (
+    SOURCE TEXT PRIMARY KEY,
+    ETAG TEXT DEFAULT NULL,
+    LAST_MODIFIED TEXT DEFAULT NULL,
+    SHA256 VARCHAR(64) NOT NULL,
+    UPDATED_AT TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
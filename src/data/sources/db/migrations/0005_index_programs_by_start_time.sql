@@ -0,0 +1,7 @@
+-- A program is identified by its channel and start time, older guides may hold duplicates
+DELETE FROM PROGRAMS DUPLICATE USING PROGRAMS ORIGINAL
+WHERE DUPLICATE.ID > ORIGINAL.ID
+AND DUPLICATE.CHANNEL_ID = ORIGINAL.CHANNEL_ID
+AND DUPLICATE.START_TIME = ORIGINAL.START_TIME;
+CREATE UNIQUE INDEX IF NOT EXISTS PROGRAMS_CHANNEL_START_TIME ON PROGRAMS (CHANNEL_ID, START_TIME);
+CREATE INDEX IF NOT EXISTS PROGRAMS_STAGING_CHANNEL_START_TIME ON PROGRAMS_STAGING (CHANNEL_ID, START_TIME);
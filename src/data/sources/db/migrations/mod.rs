@@ -0,0 +1,88 @@
+///
+/// A forward change of the database schema, applied once and recorded in `SCHEMA_MIGRATIONS`
+///
+#[derive(Debug, PartialEq)]
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+///
+/// Every migration of the PostgreSQL schema, in the order they are applied.
+/// A released migration must never be edited, add a new one instead.
+///
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_guide",
+        sql: include_str!("0001_create_guide.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "create_staging",
+        sql: include_str!("0002_create_staging.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "create_imports",
+        sql: include_str!("0003_create_imports.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "create_source_states",
+        sql: include_str!("0004_create_source_states.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "index_programs_by_start_time",
+        sql: include_str!("0005_index_programs_by_start_time.sql"),
+    },
+];
+
+///
+/// The `migrations` which are not part of the `applied` versions yet, in order
+///
+pub fn pending(migrations: &'static [Migration], applied: &[i32]) -> Vec<&'static Migration> {
+    migrations
+        .iter()
+        .filter(|m| !applied.contains(&m.version))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for (previous, next) in MIGRATIONS.iter().zip(MIGRATIONS.iter().skip(1)) {
+            assert!(
+                previous.version < next.version,
+                "Migration {} should come before {}",
+                previous.version,
+                next.version
+            );
+        }
+    }
+
+    #[test]
+    fn test_pending_skips_applied() {
+        // GIVEN
+        let applied = vec![1, 2, 3];
+
+        // WHEN
+        let pending = pending(MIGRATIONS, &applied);
+
+        // THEN
+        assert_eq!(
+            pending.iter().map(|m| m.version).collect::<Vec<i32>>(),
+            vec![4, 5]
+        );
+    }
+
+    #[test]
+    fn test_pending_on_empty_database() {
+        assert_eq!(pending(MIGRATIONS, &[]).len(), MIGRATIONS.len());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod migrations;
+pub mod pool;
 pub mod postgres_client;
-pub mod schema;
 pub mod sql_queries;
@@ -1,276 +1,544 @@
-use crate::data::converters::program_converter;
-use crate::data::sources::db::schema::SCHEMA_CREATION_QUERY;
+use crate::data::converters::{
+    channel_converter, import_converter, program_converter, source_state_converter,
+};
+use crate::data::errors::DatabaseError;
+use crate::data::sources::db::migrations::{self, Migration};
+use crate::data::sources::db::pool::DbPool;
 use crate::data::sources::db::sql_queries::{
-    DELETE_CHANNELS_QUERY, DELETE_PACKAGES_QUERY, DELETE_PROGRAMS_QUERY,
-    FIND_CURRENT_PROGRAM_BY_CHANNEL_ID_QUERY, FIND_PROGRAMS_BY_CHANNEL_ID_QUERY,
-    FIND_TONIGHT_PROGRAM_BY_CHANNEL_ID_QUERY, INSERT_CHANNEL_QUERY, INSERT_PACKAGE_QUERY,
-    SELECT_ALL_CHANNELS_QUERY, SELECT_CHANNELS_QUERY,
+    COPY_STAGING_CHANNELS_QUERY, COPY_STAGING_PACKAGES_QUERY, COPY_STAGING_PROGRAMS_QUERY,
+    COUNT_ALL_CHANNELS_QUERY, COUNT_CHANNELS_BY_IDS_QUERY, COUNT_CHANNELS_QUERY,
+    COUNT_PROGRAMS_BY_CHANNEL_ID_QUERY, COUNT_SEARCH_PROGRAMS_QUERY,
+    CREATE_SCHEMA_MIGRATIONS_QUERY, DELETE_PACKAGES_QUERY, DELETE_SOURCE_STATES_QUERY,
+    DELETE_STAGING_CHANNELS_QUERY, DELETE_STAGING_PACKAGE_QUERY, DELETE_STAGING_PROGRAMS_QUERY,
+    DELETE_VANISHED_CHANNELS_QUERY, DELETE_VANISHED_PROGRAMS_QUERY,
+    FIND_CHANNELS_WITH_UPCOMING_PROGRAMS_QUERY, FIND_CURRENT_PROGRAM_BY_CHANNEL_ID_QUERY,
+    FIND_PROGRAMS_BY_CHANNEL_ID_QUERY, FIND_PROGRAMS_BY_CHANNEL_IDS_QUERY,
+    FIND_TONIGHT_PROGRAMS_BY_CHANNEL_ID_QUERY, INSERT_IMPORT_QUERY, INSERT_SCHEMA_MIGRATION_QUERY,
+    INSERT_SOURCE_STATE_QUERY, LOCK_SCHEMA_MIGRATIONS_QUERY, PUBLISH_CHANNELS_QUERY,
+    PUBLISH_PACKAGES_QUERY, PUBLISH_PROGRAMS_QUERY, SCHEMA_MIGRATIONS_EXISTS_QUERY,
+    SEARCH_PROGRAMS_QUERY, SELECT_ALL_CHANNELS_QUERY, SELECT_CHANNELS_BY_IDS_QUERY,
+    SELECT_CHANNELS_QUERY, SELECT_IMPORTS_QUERY, SELECT_SCHEMA_MIGRATIONS_QUERY,
+    SELECT_SOURCE_STATES_QUERY, TRUNCATE_STAGING_QUERY,
 };
 use crate::domain::entities::channel::Channel;
+use crate::domain::entities::import_report::{GuideChanges, ImportReport};
+use crate::domain::entities::page::{PageRequest, Paged};
 use crate::domain::entities::program::Program;
-use crate::domain::entities::rating::Rating;
-use chrono::Timelike;
-use dotenv::var;
-use postgres::{Client, Error, NoTls};
+use crate::domain::entities::source_state::SourceState;
+use crate::domain::entities::time_window::TimeWindow;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::pin::pin;
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use tokio_postgres::types::Type;
 
 ///
-/// Get a database connection
+/// Apply the pending migrations in a single transaction, so that a failing
+/// one leaves the schema untouched
 ///
-pub fn client() -> Client {
-    let connection_string =
-        var("CONNECTION_STRING").expect("DATABASE_URL must be set in the environment variables");
-    Client::connect(connection_string.as_str(), NoTls).expect("Unable to connect to the database")
+pub async fn run_migrations(pool: &DbPool) -> Result<(), DatabaseError> {
+    println!("Migrating database schema...");
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    transaction
+        .execute(LOCK_SCHEMA_MIGRATIONS_QUERY, &[])
+        .await?;
+    transaction
+        .batch_execute(CREATE_SCHEMA_MIGRATIONS_QUERY)
+        .await?;
+    let applied = applied_migrations(&transaction).await?;
+    if let Some(latest) = applied.last()
+        && migrations::MIGRATIONS.iter().all(|m| m.version < *latest)
+    {
+        eprintln!(
+            "The database schema is at version {}, newer than this application knows about.",
+            latest
+        );
+    }
+    for migration in migrations::pending(migrations::MIGRATIONS, &applied) {
+        println!(
+            "Applying migration {} {}...",
+            migration.version, migration.name
+        );
+        transaction.batch_execute(migration.sql).await?;
+        transaction
+            .execute(
+                INSERT_SCHEMA_MIGRATION_QUERY,
+                &[&migration.version, &migration.name],
+            )
+            .await?;
+    }
+    transaction.commit().await?;
+    println!("Database schema is up to date.");
+    Ok(())
 }
 
-pub fn init_schema() {
-    thread_exec(move || -> Result<(), Error> {
-        println!("Initializing database schema...");
-        let mut client = client();
-        client.batch_execute(SCHEMA_CREATION_QUERY)?;
-        Ok(())
-    })
-    .expect("Unable to initialize database schema");
-    println!("Database schema initialized.");
+///
+/// Get the migrations which are not applied yet, without touching the database
+///
+pub async fn find_pending_migrations(
+    pool: &DbPool,
+) -> Result<Vec<&'static Migration>, DatabaseError> {
+    let client = pool.get().await?;
+    let exists: bool = client
+        .query_one(SCHEMA_MIGRATIONS_EXISTS_QUERY, &[])
+        .await?
+        .get(0);
+    let applied = if exists {
+        applied_migrations(&*client).await?
+    } else {
+        vec![]
+    };
+    Ok(migrations::pending(migrations::MIGRATIONS, &applied))
 }
 
-fn thread_exec<F, R>(f: F) -> R
-where
-    F: FnOnce() -> R + Send + 'static,
-    R: Send + 'static,
-{
-    std::thread::spawn(move || f())
-        .join()
-        .expect("Unable to execute query")
+async fn applied_migrations(
+    client: &impl tokio_postgres::GenericClient,
+) -> Result<Vec<i32>, DatabaseError> {
+    let rows = client.query(SELECT_SCHEMA_MIGRATIONS_QUERY, &[]).await?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
 }
 
-pub fn drop_channels() {
-    println!("Dropping all channels from the database...");
-    thread_exec(|| -> Result<(), Error> {
-        let mut client = client();
-        client.execute(DELETE_PACKAGES_QUERY, &[])?;
-        client.execute(DELETE_CHANNELS_QUERY, &[])?;
-        Ok(())
-    })
-    .expect("Unable to drop channels from the database");
-    println!("All channels dropped from the database.");
+///
+/// Empty the staging tables before loading a new guide
+///
+pub async fn clear_staging(pool: &DbPool) -> Result<(), DatabaseError> {
+    println!("Clearing staging tables...");
+    let client = pool.get().await?;
+    client.batch_execute(TRUNCATE_STAGING_QUERY).await?;
+    Ok(())
 }
 
-pub fn save_channels(channels: Vec<Channel>) {
-    thread_exec(move || -> Result<(), Error> {
-        let mut client = client();
-        for channel in &channels {
-            println!("Inserting channel: {}", channel.channel_id);
-            client.execute(
-                INSERT_CHANNEL_QUERY,
-                &[&channel.channel_id, &channel.name, &channel.icon_url],
-            )?;
-        }
-        Ok(())
-    })
-    .expect("Unable to save channels to the database");
+///
+/// Remove what was staged for a package which could not be imported entirely,
+/// `channel_ids` being the channels first declared by this package
+///
+pub async fn discard_staged_package(
+    pool: &DbPool,
+    package: &str,
+    channel_ids: &[String],
+) -> Result<(), DatabaseError> {
+    println!("Discarding staged package {}...", package);
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    transaction
+        .execute(DELETE_STAGING_PACKAGE_QUERY, &[&package])
+        .await?;
+    transaction
+        .execute(DELETE_STAGING_CHANNELS_QUERY, &[&channel_ids])
+        .await?;
+    transaction
+        .execute(DELETE_STAGING_PROGRAMS_QUERY, &[&channel_ids])
+        .await?;
+    Ok(transaction.commit().await?)
 }
 
-pub fn save_channel_packages(channels: Vec<Channel>, package: String) {
-    thread_exec(move || -> Result<(), Error> {
-        let mut client = client();
-        for channel in &channels {
-            println!(
-                "Inserting channel package for channel_id: {}",
-                channel.channel_id
-            );
-            client.execute(INSERT_PACKAGE_QUERY, &[&channel.channel_id, &package])?;
-        }
-        Ok(())
-    })
-    .expect("Unable to save channel packages to the database");
+///
+/// Make the live channels, packages and programs match the staged ones
+/// in a single transaction, along with the state of the sources they were
+/// read from. Only the changed rows are written, so ids survive refreshes.
+/// On failure the previous guide is kept.
+///
+pub async fn publish_staging(
+    pool: &DbPool,
+    source_states: &[SourceState],
+) -> Result<GuideChanges, DatabaseError> {
+    println!("Publishing staged guide...");
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    transaction.execute(PUBLISH_CHANNELS_QUERY, &[]).await?;
+    let row = transaction.query_one(PUBLISH_PROGRAMS_QUERY, &[]).await?;
+    let deleted = transaction
+        .execute(DELETE_VANISHED_PROGRAMS_QUERY, &[])
+        .await?;
+    let changes = GuideChanges {
+        added: row.get::<_, i64>(0) as usize,
+        updated: row.get::<_, i64>(1) as usize,
+        deleted: deleted as usize,
+    };
+    transaction.execute(DELETE_PACKAGES_QUERY, &[]).await?;
+    transaction.execute(PUBLISH_PACKAGES_QUERY, &[]).await?;
+    transaction
+        .execute(DELETE_VANISHED_CHANNELS_QUERY, &[])
+        .await?;
+    transaction.execute(DELETE_SOURCE_STATES_QUERY, &[]).await?;
+    for state in source_states {
+        transaction
+            .execute(
+                INSERT_SOURCE_STATE_QUERY,
+                &[
+                    &state.source,
+                    &state.etag,
+                    &state.last_modified,
+                    &state.sha256,
+                ],
+            )
+            .await?;
+    }
+    transaction.commit().await?;
+    client.batch_execute(TRUNCATE_STAGING_QUERY).await?;
+    println!(
+        "Staged guide published: {} programs added, {} updated, {} deleted.",
+        changes.added, changes.updated, changes.deleted
+    );
+    Ok(changes)
 }
 
-pub fn find_all_channels() -> Vec<Channel> {
-    thread_exec(move || -> Result<Vec<Channel>, Error> {
-        let mut channels = Vec::new();
-        let select = client().query(SELECT_ALL_CHANNELS_QUERY, &[])?;
-        for row in select {
-            let channel = Channel {
-                id: row.get(0),
-                channel_id: row.get(1),
-                name: row.get(2),
-                icon_url: row.get(3),
-            };
-            channels.push(channel);
-        }
-        Ok(channels)
+pub async fn stage_channels(pool: &DbPool, channels: &[Channel]) -> Result<(), DatabaseError> {
+    let client = pool.get().await?;
+    let sink = client.copy_in(COPY_STAGING_CHANNELS_QUERY).await?;
+    let mut writer = pin!(BinaryCopyInWriter::new(
+        sink,
+        &[Type::VARCHAR, Type::VARCHAR, Type::TEXT]
+    ));
+    for channel in channels {
+        writer
+            .as_mut()
+            .write(&[&channel.channel_id, &channel.name, &channel.icon_url])
+            .await?;
+    }
+    writer.finish().await?;
+    Ok(())
+}
+
+pub async fn stage_channel_packages(
+    pool: &DbPool,
+    channels: &[Channel],
+    package: &str,
+) -> Result<(), DatabaseError> {
+    let client = pool.get().await?;
+    let sink = client.copy_in(COPY_STAGING_PACKAGES_QUERY).await?;
+    let mut writer = pin!(BinaryCopyInWriter::new(
+        sink,
+        &[Type::VARCHAR, Type::VARCHAR]
+    ));
+    for channel in channels {
+        writer
+            .as_mut()
+            .write(&[&channel.channel_id, &package])
+            .await?;
+    }
+    writer.finish().await?;
+    Ok(())
+}
+
+pub async fn find_all_channels(
+    pool: &DbPool,
+    page: PageRequest,
+) -> Result<Paged<Channel>, DatabaseError> {
+    let client = pool.get().await?;
+    let rows = client
+        .query(SELECT_ALL_CHANNELS_QUERY, &[&page.limit(), &page.offset()])
+        .await?;
+    let total: i64 = client
+        .query_one(COUNT_ALL_CHANNELS_QUERY, &[])
+        .await?
+        .get(0);
+    Ok(Paged {
+        items: rows.iter().map(row_to_channel).collect(),
+        total: total as u64,
     })
-    .expect("Unable to find all channels")
 }
 
-pub fn find_channels_by_package(package: String) -> Vec<Channel> {
-    thread_exec(move || -> Result<Vec<Channel>, Error> {
-        let mut channels = Vec::new();
-        let select = client().query(SELECT_CHANNELS_QUERY, &[&package])?;
-        for row in select {
-            let channel = Channel {
-                id: row.get(0),
-                channel_id: row.get(1),
-                name: row.get(2),
-                icon_url: row.get(3),
-            };
-            channels.push(channel);
-        }
-        Ok(channels)
+pub async fn find_channels_by_package(
+    pool: &DbPool,
+    package: &str,
+    page: PageRequest,
+) -> Result<Paged<Channel>, DatabaseError> {
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            SELECT_CHANNELS_QUERY,
+            &[&package, &page.limit(), &page.offset()],
+        )
+        .await?;
+    let total: i64 = client
+        .query_one(COUNT_CHANNELS_QUERY, &[&package])
+        .await?
+        .get(0);
+    Ok(Paged {
+        items: rows.iter().map(row_to_channel).collect(),
+        total: total as u64,
     })
-    .expect("Unable to find channels by package")
 }
 
-pub fn drop_programs() {
-    println!("Dropping all programs from the database...");
-    thread_exec(move || -> Result<(), Error> {
-        let mut client = client();
-        client.execute(DELETE_PROGRAMS_QUERY, &[])?;
-        Ok(())
+pub async fn find_channels_by_ids(
+    pool: &DbPool,
+    channel_ids: &[String],
+    page: PageRequest,
+) -> Result<Paged<Channel>, DatabaseError> {
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            SELECT_CHANNELS_BY_IDS_QUERY,
+            &[&channel_ids, &page.limit(), &page.offset()],
+        )
+        .await?;
+    let total: i64 = client
+        .query_one(COUNT_CHANNELS_BY_IDS_QUERY, &[&channel_ids])
+        .await?
+        .get(0);
+    Ok(Paged {
+        items: rows.iter().map(row_to_channel).collect(),
+        total: total as u64,
     })
-    .expect("Unable to drop programs from the database");
 }
 
-pub fn bulk_insert_programs(programs: Vec<Program>) {
+fn row_to_channel(row: &tokio_postgres::Row) -> Channel {
+    Channel {
+        id: row.get(0),
+        channel_id: row.get(1),
+        name: row.get(2),
+        icon_url: row.get(3),
+    }
+}
+
+pub async fn bulk_stage_programs(pool: &DbPool, programs: &[Program]) -> Result<(), DatabaseError> {
     println!(
         "Bulk inserting {} programs to the database...",
         programs.len()
     );
-    thread_exec(move || -> Result<(), Error> {
-        let mut client = client();
-        let insert_query = "INSERT INTO PROGRAMS (\
-CHANNEL_ID, START_TIME, END_TIME, TITLE, SUBTITLE, DESCRIPTION, CATEGORIES, \
-ICON, EPISODE_NUM, RATING_SYSTEM, RATING_VALUE, RATING_ICON) \
-VALUES ";
-        let values: Vec<String> = programs
-            .iter()
-            .map(|program| {
-                let rating = program.rating.as_ref().unwrap_or_else(|| &Rating {
-                    icon: None,
-                    system: None,
-                    value: None,
-                });
-                format!(
-                    "('{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}', '{}')",
-                    program.channel_id,
-                    program.start_time.naive_utc(),
-                    program.end_time.naive_utc(),
-                    escape_string(&program.title),
-                    escape_string(program.sub_title.as_deref().unwrap_or("")),
-                    escape_string(program.description.as_deref().unwrap_or("")),
-                    escape_string(
-                        program
-                            .categories
-                            .as_deref()
-                            .unwrap_or(&vec![])
-                            .join(",")
-                            .as_str()
-                    ),
-                    escape_string(program.icon_url.as_deref().unwrap_or("")),
-                    escape_string(program.episode_num.as_deref().unwrap_or("")),
-                    escape_string(rating.system.as_ref().unwrap_or(&"".to_string()).as_str()),
-                    escape_string(rating.value.as_ref().unwrap_or(&"".to_string()).as_str()),
-                    escape_string(rating.icon.as_ref().unwrap_or(&"".to_string()).as_str())
-                )
-            })
-            .collect();
-        let full_query = format!("{} {}", insert_query, values.join(", "));
-        //println!("Executing bulk insert query: {}", full_query);
-        client.batch_execute(&full_query)?;
-        println!("Bulk insert completed.");
-        Ok(())
+    let client = pool.get().await?;
+    let sink = client.copy_in(COPY_STAGING_PROGRAMS_QUERY).await?;
+    let mut writer = pin!(BinaryCopyInWriter::new(
+        sink,
+        &[
+            Type::VARCHAR,
+            Type::TIMESTAMP,
+            Type::TIMESTAMP,
+            Type::TEXT,
+            Type::TEXT,
+            Type::TEXT,
+            Type::VARCHAR,
+            Type::TEXT,
+            Type::VARCHAR,
+            Type::VARCHAR,
+            Type::VARCHAR,
+            Type::TEXT,
+        ],
+    ));
+    for program in programs {
+        // Missing values are stored as empty strings, as the guide always did
+        let categories = program.categories.as_deref().unwrap_or_default().join(",");
+        let rating = program.rating.as_ref();
+        writer
+            .as_mut()
+            .write(&[
+                &program.channel_id,
+                &program.start_time.naive_utc(),
+                &program.end_time.naive_utc(),
+                &program.title,
+                &program.sub_title.as_deref().unwrap_or(""),
+                &program.description.as_deref().unwrap_or(""),
+                &categories,
+                &program.icon_url.as_deref().unwrap_or(""),
+                &program.episode_num.as_deref().unwrap_or(""),
+                &rating.and_then(|r| r.system.as_deref()).unwrap_or(""),
+                &rating.and_then(|r| r.value.as_deref()).unwrap_or(""),
+                &rating.and_then(|r| r.icon.as_deref()).unwrap_or(""),
+            ])
+            .await?;
+    }
+    writer.finish().await?;
+    println!("Bulk insert completed.");
+    Ok(())
+}
+
+pub async fn find_programs_by_channel_id(
+    pool: &DbPool,
+    channel_id: &str,
+    window: TimeWindow,
+    page: PageRequest,
+) -> Result<Paged<Program>, DatabaseError> {
+    let client = pool.get().await?;
+    let from = window.from.naive_utc();
+    let to = window.to.map(|to| to.naive_utc());
+    let rows = client
+        .query(
+            FIND_PROGRAMS_BY_CHANNEL_ID_QUERY,
+            &[&channel_id, &from, &to, &page.limit(), &page.offset()],
+        )
+        .await?;
+    let total: i64 = client
+        .query_one(
+            COUNT_PROGRAMS_BY_CHANNEL_ID_QUERY,
+            &[&channel_id, &from, &to],
+        )
+        .await?
+        .get(0);
+    Ok(Paged {
+        items: rows.iter().map(program_converter::row_to_entity).collect(),
+        total: total as u64,
     })
-    .expect("Unable to bulk insert programs to the database");
 }
 
-pub fn escape_string(input: &str) -> String {
-    input.replace('\'', "''")
+pub async fn find_programs_by_channel_ids(
+    pool: &DbPool,
+    channel_ids: &[String],
+    window: TimeWindow,
+) -> Result<Vec<Program>, DatabaseError> {
+    let client = pool.get().await?;
+    let from = window.from.naive_utc();
+    let to = window.to.map(|to| to.naive_utc());
+    let rows = client
+        .query(
+            FIND_PROGRAMS_BY_CHANNEL_IDS_QUERY,
+            &[&channel_ids, &from, &to],
+        )
+        .await?;
+    Ok(rows.iter().map(program_converter::row_to_entity).collect())
 }
 
-pub fn find_programs_by_channel_id(channel_id: &str) -> Vec<Program> {
-    let channel = String::from(channel_id);
-    thread_exec(move || -> Result<Vec<Program>, Error> {
-        let mut programs = Vec::new();
-        let mut client = client();
-        let rows = client.query(FIND_PROGRAMS_BY_CHANNEL_ID_QUERY, &[&channel])?;
-        for row in rows {
-            let program = program_converter::row_to_entity(&row);
-            programs.push(program);
-        }
-        Ok(programs)
-    })
-    .expect(&format!(
-        "Cannot find programs for channel id {}",
-        channel_id
+pub async fn find_channels_with_upcoming_programs(
+    pool: &DbPool,
+    package: Option<&str>,
+    at: DateTime<Utc>,
+    count: u32,
+    page: PageRequest,
+) -> Result<Paged<(Channel, Vec<Program>)>, DatabaseError> {
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            FIND_CHANNELS_WITH_UPCOMING_PROGRAMS_QUERY,
+            &[
+                &package,
+                &at.naive_utc(),
+                &(count as i64),
+                &page.limit(),
+                &page.offset(),
+            ],
+        )
+        .await?;
+    let total: i64 = rows.first().map_or(0, |row| row.get(17));
+    let rows = rows
+        .iter()
+        .filter(|row| row.get::<_, Option<i32>>(13).is_some())
+        .map(|row| {
+            let channel = Channel {
+                id: row.get(13),
+                channel_id: row.get(14),
+                name: row.get(15),
+                icon_url: row.get(16),
+            };
+            let program = row
+                .get::<_, Option<i32>>(0)
+                .map(|_| program_converter::row_to_entity(row));
+            (channel, program)
+        })
+        .collect();
+    Ok(channel_converter::rows_to_channels_with_programs(
+        rows,
+        total as u64,
     ))
 }
 
-pub fn find_current_program_by_channel_id(channel_id: &str) -> Program {
-    let channel = String::from(channel_id);
-    thread_exec(move || -> Result<Program, Error> {
-        let mut client = client();
-        let row = client.query_one(FIND_CURRENT_PROGRAM_BY_CHANNEL_ID_QUERY, &[&channel])?;
-        let program = program_converter::row_to_entity(&row);
-        Ok(program)
-    })
-    .expect(&format!(
-        "Unable to find current program by channel id {}",
-        channel_id
-    ))
+pub async fn find_current_program_by_channel_id(
+    pool: &DbPool,
+    channel_id: &str,
+) -> Result<Option<Program>, DatabaseError> {
+    let client = pool.get().await?;
+    let now = Utc::now().naive_utc();
+    let row = client
+        .query_opt(
+            FIND_CURRENT_PROGRAM_BY_CHANNEL_ID_QUERY,
+            &[&channel_id, &now],
+        )
+        .await?;
+    Ok(row.as_ref().map(program_converter::row_to_entity))
 }
 
-pub fn find_tonight_program_by_channel_id(channel_id: &str) -> Program {
-    let channel = String::from(channel_id);
-    thread_exec(move || -> Result<Program, Error> {
-        let mut client = client();
-        // Tonight 20:30
-        let target_time = chrono::Local::now()
-            .with_hour(20)
-            .and_then(|dt| dt.with_minute(30))
-            .unwrap_or_else(|| chrono::Local::now());
-        let row = client.query_one(
-            FIND_TONIGHT_PROGRAM_BY_CHANNEL_ID_QUERY,
-            &[&channel, &target_time.naive_utc()],
-        )?;
-        let program = program_converter::row_to_entity(&row);
-        Ok(program)
-    })
-    .expect(&format!(
-        "Unable to find tonight program by channel id {}",
-        channel_id
-    ))
+pub async fn find_tonight_programs_by_channel_id(
+    pool: &DbPool,
+    channel_id: &str,
+    from: DateTime<Utc>,
+    min_duration: TimeDelta,
+    count: u32,
+) -> Result<Vec<Program>, DatabaseError> {
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            FIND_TONIGHT_PROGRAMS_BY_CHANNEL_ID_QUERY,
+            &[
+                &channel_id,
+                &from.naive_utc(),
+                &(min_duration.num_seconds() as f64),
+                &(count as i64),
+            ],
+        )
+        .await?;
+    Ok(rows.iter().map(program_converter::row_to_entity).collect())
 }
 
-pub fn search_programs(query_string: String) -> Vec<Program> {
-    if !query_valid(query_string.clone()) {
-        println!("Invalid query string: {}", query_string);
-        return vec![];
-    }
-    thread_exec(move || -> Result<Vec<Program>, Error> {
-        let mut programs = Vec::new();
-        let mut client = client();
-        let query = format!("%{}%", query_string);
-        let rows = client
-            .query(
-                "SELECT * FROM PROGRAMS WHERE TITLE ILIKE $1 OR SUBTITLE ILIKE $1 OR DESCRIPTION ILIKE $1",
-                &[&query],
-            )
-            ?;
-        for row in rows {
-            let program = program_converter::row_to_entity(&row);
-            programs.push(program);
-        }
-        Ok(programs)
+pub async fn search_programs(
+    pool: &DbPool,
+    query_string: &str,
+    page: PageRequest,
+) -> Result<Paged<Program>, DatabaseError> {
+    let client = pool.get().await?;
+    let query = format!("%{}%", query_string);
+    let rows = client
+        .query(
+            SEARCH_PROGRAMS_QUERY,
+            &[&query, &page.limit(), &page.offset()],
+        )
+        .await?;
+    let total: i64 = client
+        .query_one(COUNT_SEARCH_PROGRAMS_QUERY, &[&query])
+        .await?
+        .get(0);
+    Ok(Paged {
+        items: rows.iter().map(program_converter::row_to_entity).collect(),
+        total: total as u64,
     })
-    .expect("Unable to search programs by query string")
 }
 
-fn query_valid(query: String) -> bool {
-    // The only allowed characers a lower case letters (ASCII 97-122)
-    for c in query.chars() {
-        if !(c.is_ascii_lowercase() || c.is_ascii_whitespace()) {
-            return false;
-        }
-    }
-    true
+///
+/// Save the report of an import and return its id
+///
+pub async fn save_import(pool: &DbPool, report: &ImportReport) -> Result<i32, DatabaseError> {
+    let client = pool.get().await?;
+    let json = serde_json::to_value(report).unwrap_or_default();
+    let row = client
+        .query_one(
+            INSERT_IMPORT_QUERY,
+            &[
+                &report.started_at,
+                &report.finished_at,
+                &(report.duration_ms as i64),
+                &report.succeeded(),
+                &(report.parsed as i32),
+                &(report.skipped as i32),
+                &(report.inserted as i32),
+                &report.error,
+                &json,
+            ],
+        )
+        .await?;
+    Ok(row.get(0))
+}
+
+pub async fn find_imports(pool: &DbPool, limit: i64) -> Result<Vec<ImportReport>, DatabaseError> {
+    let rows = pool
+        .get()
+        .await?
+        .query(SELECT_IMPORTS_QUERY, &[&limit])
+        .await?;
+    Ok(rows
+        .iter()
+        .filter_map(import_converter::row_to_entity)
+        .collect())
+}
+
+///
+/// Get the state of the sources the published guide was read from
+///
+pub async fn find_source_states(pool: &DbPool) -> Result<Vec<SourceState>, DatabaseError> {
+    let rows = pool
+        .get()
+        .await?
+        .query(SELECT_SOURCE_STATES_QUERY, &[])
+        .await?;
+    Ok(rows
+        .iter()
+        .map(source_state_converter::row_to_entity)
+        .collect())
 }
@@ -1,35 +1,95 @@
 ///
-/// Get all the channel items from the database
+/// Get a page of the channel items from the database
 ///
 pub const SELECT_ALL_CHANNELS_QUERY: &str = "\
 SELECT channels.id, channels.channel_id, channels.display_name, channels.icon \
 FROM channels \
+ORDER BY channels.channel_id \
+LIMIT $1 OFFSET $2 \
 ";
 
 ///
-/// Get channel items from the database with a specific package_id
+/// Count the channel items of the database
+///
+pub const COUNT_ALL_CHANNELS_QUERY: &str = "SELECT COUNT(*) FROM channels";
+
+///
+/// Get a page of the channel items from the database with a specific package_id
 ///
 pub const SELECT_CHANNELS_QUERY: &str = "\
 SELECT channels.id, channels.channel_id, channels.display_name, channels.icon \
 FROM channels \
 JOIN channel_packages ON channels.channel_id = channel_packages.channel_id \
 WHERE channel_packages.package_id = $1 \
+ORDER BY channels.channel_id \
+LIMIT $2 OFFSET $3 \
+";
+
+///
+/// Count the channel items with a specific package_id
+///
+pub const COUNT_CHANNELS_QUERY: &str = "\
+SELECT COUNT(*) FROM channels \
+JOIN channel_packages ON channels.channel_id = channel_packages.channel_id \
+WHERE channel_packages.package_id = $1 \
+";
+
+///
+/// Get a page of the channel items with the given channel ids
+///
+pub const SELECT_CHANNELS_BY_IDS_QUERY: &str = "\
+SELECT channels.id, channels.channel_id, channels.display_name, channels.icon \
+FROM channels \
+WHERE channels.channel_id = ANY($1) \
+ORDER BY channels.channel_id \
+LIMIT $2 OFFSET $3 \
+";
+
+///
+/// Count the channel items with the given channel ids
+///
+pub const COUNT_CHANNELS_BY_IDS_QUERY: &str =
+    "SELECT COUNT(*) FROM channels WHERE channel_id = ANY($1)";
+
+///
+/// Get a page of the programs matching a pattern, ignoring case
+///
+pub const SEARCH_PROGRAMS_QUERY: &str = "\
+SELECT * FROM programs \
+WHERE title ILIKE $1 OR subtitle ILIKE $1 OR description ILIKE $1 \
+ORDER BY channel_id, start_time \
+LIMIT $2 OFFSET $3 \
+";
+
+///
+/// Count the programs matching a pattern, ignoring case
+///
+pub const COUNT_SEARCH_PROGRAMS_QUERY: &str = "\
+SELECT COUNT(*) FROM programs \
+WHERE title ILIKE $1 OR subtitle ILIKE $1 OR description ILIKE $1 \
 ";
 
 ///
-/// Insert a new channel into the database
+/// Load channels into the staging table
 ///
-pub const INSERT_CHANNEL_QUERY: &str = "INSERT INTO channels (channel_id, display_name, icon) VALUES ($1, $2, $3)";
+pub const COPY_STAGING_CHANNELS_QUERY: &str =
+    "COPY channels_staging (channel_id, display_name, icon) FROM STDIN BINARY";
 
 ///
-/// Delete all channels from the database
+/// Load channel packages into the staging table
 ///
-pub const DELETE_CHANNELS_QUERY: &str = "DELETE FROM channels";
+pub const COPY_STAGING_PACKAGES_QUERY: &str =
+    "COPY channel_packages_staging (channel_id, package_id) FROM STDIN BINARY";
 
 ///
-/// Insert a new package for a channel into the database
+/// Load programs into the staging table
 ///
-pub const INSERT_PACKAGE_QUERY: &str = "INSERT INTO channel_packages (channel_id, package_id) VALUES ($1, $2)";
+pub const COPY_STAGING_PROGRAMS_QUERY: &str = "\
+COPY programs_staging (\
+channel_id, start_time, end_time, title, subtitle, description, categories, \
+icon, episode_num, rating_system, rating_value, rating_icon) \
+FROM STDIN BINARY\
+";
 
 ///
 /// Delete all packages from the database
@@ -37,37 +97,250 @@ pub const INSERT_PACKAGE_QUERY: &str = "INSERT INTO channel_packages (channel_id
 pub const DELETE_PACKAGES_QUERY: &str = "DELETE FROM channel_packages";
 
 ///
-/// Delete all programs from the database
+/// Empty the staging tables before a new import
+///
+pub const TRUNCATE_STAGING_QUERY: &str =
+    "TRUNCATE channels_staging, channel_packages_staging, programs_staging";
+
+///
+/// Remove the staged memberships of a package
+///
+pub const DELETE_STAGING_PACKAGE_QUERY: &str =
+    "DELETE FROM channel_packages_staging WHERE package_id = $1";
+
+///
+/// Remove the staged channels with the given channel ids
+///
+pub const DELETE_STAGING_CHANNELS_QUERY: &str =
+    "DELETE FROM channels_staging WHERE channel_id = ANY($1)";
+
+///
+/// Remove the staged programs of the given channel ids
+///
+pub const DELETE_STAGING_PROGRAMS_QUERY: &str =
+    "DELETE FROM programs_staging WHERE channel_id = ANY($1)";
+
+///
+/// Insert the new staged channels into the live table and update the changed ones,
+/// so that channel ids survive refreshes
 ///
-pub const DELETE_PROGRAMS_QUERY: &str = "DELETE FROM programs";
+pub const PUBLISH_CHANNELS_QUERY: &str = "\
+INSERT INTO channels (channel_id, display_name, icon) \
+SELECT DISTINCT ON (channel_id) channel_id, display_name, icon \
+FROM channels_staging \
+ORDER BY channel_id \
+ON CONFLICT (channel_id) DO UPDATE \
+SET display_name = EXCLUDED.display_name, icon = EXCLUDED.icon \
+WHERE (channels.display_name, channels.icon) \
+IS DISTINCT FROM (EXCLUDED.display_name, EXCLUDED.icon) \
+";
 
 ///
-/// Get all programs for a specific channel by channel_id
+/// Delete the live channels which are no longer staged
+///
+pub const DELETE_VANISHED_CHANNELS_QUERY: &str = "\
+DELETE FROM channels \
+WHERE NOT EXISTS (\
+SELECT 1 FROM channels_staging WHERE channels_staging.channel_id = channels.channel_id) \
+";
+
+///
+/// Copy the staged channel packages into the live table
+///
+pub const PUBLISH_PACKAGES_QUERY: &str = "\
+INSERT INTO channel_packages (channel_id, package_id) \
+SELECT DISTINCT channel_id, package_id \
+FROM channel_packages_staging \
+";
+
+///
+/// Insert the new staged programs into the live table and update the changed ones,
+/// a program being identified by its channel and start time.
+/// Returns the number of inserted and updated programs.
+///
+pub const PUBLISH_PROGRAMS_QUERY: &str = "\
+WITH upserted AS (\
+INSERT INTO programs (\
+channel_id, start_time, end_time, title, subtitle, description, categories, \
+icon, episode_num, rating_system, rating_value, rating_icon) \
+SELECT DISTINCT ON (channel_id, start_time) \
+channel_id, start_time, end_time, title, subtitle, description, categories, \
+icon, episode_num, rating_system, rating_value, rating_icon \
+FROM programs_staging \
+ORDER BY channel_id, start_time \
+ON CONFLICT (channel_id, start_time) DO UPDATE \
+SET end_time = EXCLUDED.end_time, title = EXCLUDED.title, subtitle = EXCLUDED.subtitle, \
+description = EXCLUDED.description, categories = EXCLUDED.categories, icon = EXCLUDED.icon, \
+episode_num = EXCLUDED.episode_num, rating_system = EXCLUDED.rating_system, \
+rating_value = EXCLUDED.rating_value, rating_icon = EXCLUDED.rating_icon \
+WHERE (programs.end_time, programs.title, programs.subtitle, programs.description, \
+programs.categories, programs.icon, programs.episode_num, programs.rating_system, \
+programs.rating_value, programs.rating_icon) \
+IS DISTINCT FROM (EXCLUDED.end_time, EXCLUDED.title, EXCLUDED.subtitle, EXCLUDED.description, \
+EXCLUDED.categories, EXCLUDED.icon, EXCLUDED.episode_num, EXCLUDED.rating_system, \
+EXCLUDED.rating_value, EXCLUDED.rating_icon) \
+RETURNING xmax = 0 AS inserted) \
+SELECT COUNT(*) FILTER (WHERE inserted), COUNT(*) FILTER (WHERE NOT inserted) FROM upserted \
+";
+
+///
+/// Delete the live programs which are no longer staged
+///
+pub const DELETE_VANISHED_PROGRAMS_QUERY: &str = "\
+DELETE FROM programs \
+WHERE NOT EXISTS (\
+SELECT 1 FROM programs_staging \
+WHERE programs_staging.channel_id = programs.channel_id \
+AND programs_staging.start_time = programs.start_time) \
+";
+
+///
+/// Get a page of the programs of a channel on air during a period,
+/// including those started before it. No end is given as NULL.
 ///
 pub const FIND_PROGRAMS_BY_CHANNEL_ID_QUERY: &str = "\
 SELECT * FROM programs WHERE channel_id = $1 \
-AND start_time >= NOW() \
+AND end_time > $2 \
+AND ($3::timestamp IS NULL OR start_time < $3) \
 ORDER BY start_time ASC \
-LIMIT 100
+LIMIT $4 OFFSET $5
+";
+
+pub const COUNT_PROGRAMS_BY_CHANNEL_ID_QUERY: &str = "\
+SELECT COUNT(*) FROM programs WHERE channel_id = $1 \
+AND end_time > $2 \
+AND ($3::timestamp IS NULL OR start_time < $3) \
+";
+
+///
+/// Get the programs of several channels on air during a period,
+/// including those started before it. No end is given as NULL.
+///
+pub const FIND_PROGRAMS_BY_CHANNEL_IDS_QUERY: &str = "\
+SELECT * FROM programs WHERE channel_id = ANY($1) \
+AND end_time > $2 \
+AND ($3::timestamp IS NULL OR start_time < $3) \
+ORDER BY channel_id, start_time ASC \
+";
+
+///
+/// Get a page of the channels of a package, of every channel when no package is given,
+/// each joined with its first programs on air at a time or starting after it.
+/// The program columns come first and are NULL for a channel without program,
+/// a single row of NULL channel columns holds the total of an empty page.
+///
+pub const FIND_CHANNELS_WITH_UPCOMING_PROGRAMS_QUERY: &str = "\
+WITH matching AS (\
+SELECT * FROM channels \
+WHERE $1::text IS NULL \
+OR channel_id IN (SELECT channel_id FROM channel_packages WHERE package_id = $1)), \
+page AS (SELECT * FROM matching ORDER BY channel_id LIMIT $4 OFFSET $5), \
+upcoming AS (\
+SELECT * FROM (\
+SELECT *, ROW_NUMBER() OVER (PARTITION BY channel_id ORDER BY start_time) AS position \
+FROM programs \
+WHERE channel_id IN (SELECT channel_id FROM page) AND end_time > $2) AS ranked \
+WHERE position <= $3) \
+SELECT upcoming.id, upcoming.channel_id, upcoming.start_time, upcoming.end_time, upcoming.title, \
+upcoming.subtitle, upcoming.description, upcoming.categories, upcoming.icon, upcoming.episode_num, \
+upcoming.rating_system, upcoming.rating_value, upcoming.rating_icon, \
+page.id, page.channel_id, page.display_name, page.icon, \
+(SELECT COUNT(*) FROM matching) \
+FROM (SELECT 1) AS one \
+LEFT JOIN page ON TRUE \
+LEFT JOIN upcoming ON upcoming.channel_id = page.channel_id \
+ORDER BY page.channel_id, upcoming.start_time ASC \
 ";
 
 ///
-/// Get the current program for a specific channel by channel_id
+/// Get the program of a channel on air at a time
 ///
 pub const FIND_CURRENT_PROGRAM_BY_CHANNEL_ID_QUERY: &str = "\
 SELECT * FROM programs \
 WHERE channel_id = $1 \
-AND start_time <= NOW() \
-AND end_time >= NOW() \
+AND start_time <= $2 \
+AND end_time >= $2 \
 LIMIT 1
 ";
 
-pub const FIND_TONIGHT_PROGRAM_BY_CHANNEL_ID_QUERY: &str = "\
+///
+/// Get the first programs of a channel starting from a time and lasting at least
+/// a number of seconds
+///
+pub const FIND_TONIGHT_PROGRAMS_BY_CHANNEL_ID_QUERY: &str = "\
 SELECT * FROM programs
 WHERE channel_id = $1
 AND start_time >= $2
--- duration is at least 30 minutes
-AND (end_time - start_time) >= INTERVAL '30 minute'
+AND (end_time - start_time) >= make_interval(secs => $3)
 ORDER BY start_time ASC
-LIMIT 1
-";
\ No newline at end of file
+LIMIT $4
+";
+
+///
+/// Save the report of an import
+///
+pub const INSERT_IMPORT_QUERY: &str = "\
+INSERT INTO imports (started_at, finished_at, duration_ms, success, parsed, skipped, inserted, error, report) \
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+RETURNING id
+";
+
+///
+/// Get the most recent import reports
+///
+pub const SELECT_IMPORTS_QUERY: &str = "\
+SELECT id, report FROM imports \
+ORDER BY started_at DESC \
+LIMIT $1
+";
+
+///
+/// Get the state of the sources of the published guide
+///
+pub const SELECT_SOURCE_STATES_QUERY: &str =
+    "SELECT source, etag, last_modified, sha256 FROM source_states";
+
+///
+/// Delete the state of every source
+///
+pub const DELETE_SOURCE_STATES_QUERY: &str = "DELETE FROM source_states";
+
+///
+/// Save the state of a source
+///
+pub const INSERT_SOURCE_STATE_QUERY: &str =
+    "INSERT INTO source_states (source, etag, last_modified, sha256) VALUES ($1, $2, $3, $4)";
+
+///
+/// Create the table recording the applied migrations
+///
+pub const CREATE_SCHEMA_MIGRATIONS_QUERY: &str = "\
+CREATE TABLE IF NOT EXISTS schema_migrations ( \
+    version INTEGER PRIMARY KEY, \
+    name TEXT NOT NULL, \
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW() \
+)";
+
+///
+/// Prevent several instances from migrating the database at the same time,
+/// released with the transaction
+///
+pub const LOCK_SCHEMA_MIGRATIONS_QUERY: &str = "SELECT pg_advisory_xact_lock(4242)";
+
+///
+/// Check whether the migrations table exists, without creating it
+///
+pub const SCHEMA_MIGRATIONS_EXISTS_QUERY: &str =
+    "SELECT to_regclass('schema_migrations') IS NOT NULL";
+
+///
+/// Get the versions of the applied migrations
+///
+pub const SELECT_SCHEMA_MIGRATIONS_QUERY: &str =
+    "SELECT version FROM schema_migrations ORDER BY version";
+
+///
+/// Record an applied migration
+///
+pub const INSERT_SCHEMA_MIGRATION_QUERY: &str =
+    "INSERT INTO schema_migrations (version, name) VALUES ($1, $2)";
@@ -0,0 +1,49 @@
+use crate::data::errors::IngestionError;
+use crate::data::sources::xmltv;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+///
+/// List the `.xml`, `.gz`, `.xz` and `.zip` files of a directory, sorted by name
+///
+pub fn supported_files(path: &Path) -> Result<Vec<PathBuf>, IngestionError> {
+    let mut files = std::fs::read_dir(path)
+        .map_err(|e| IngestionError::Fetch(format!("{}: {}", path.display(), e)))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && xmltv::is_supported_file(&p.to_string_lossy()))
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+    if files.is_empty() {
+        return Err(IngestionError::Fetch(format!(
+            "No XMLTV file found in {}",
+            path.display()
+        )));
+    }
+    Ok(files)
+}
+
+///
+/// Compute the SHA-256 of a file, or of the names and contents
+/// of the supported files of a directory
+///
+pub fn sha256(path: &Path) -> Result<String, IngestionError> {
+    let read_error =
+        |e: std::io::Error| IngestionError::Fetch(format!("{}: {}", path.display(), e));
+    let mut hasher = Sha256::new();
+    if path.is_dir() {
+        for file in supported_files(path)? {
+            hasher.update(file.file_name().unwrap_or_default().as_encoded_bytes());
+            std::io::copy(&mut File::open(&file).map_err(read_error)?, &mut hasher)
+                .map_err(read_error)?;
+        }
+    } else {
+        std::io::copy(&mut File::open(path).map_err(read_error)?, &mut hasher)
+            .map_err(read_error)?;
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -1,2 +1,5 @@
 pub mod api;
 pub mod db;
+pub mod file;
+pub mod sqlite;
+pub mod xmltv;
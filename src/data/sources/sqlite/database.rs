@@ -0,0 +1,81 @@
+use crate::data::errors::DatabaseError;
+use dotenv::var;
+use rusqlite::Connection;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Time a statement waits for the database to be unlocked by another process
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+///
+/// A SQLite database file, with one connection for the imports and one for the requests.
+/// In WAL mode the requests keep reading the published guide while an import writes.
+///
+#[derive(Clone)]
+pub struct SqliteDatabase {
+    writer: Arc<Mutex<Connection>>,
+    reader: Arc<Mutex<Connection>>,
+}
+
+impl SqliteDatabase {
+    ///
+    /// Open the database at the `SQLITE_PATH` environment variable, `tv_prog.db` by default
+    ///
+    pub fn from_env() -> Result<SqliteDatabase, DatabaseError> {
+        let path = var("SQLITE_PATH").unwrap_or_else(|_| "tv_prog.db".to_string());
+        println!("SQLite database: {}", path);
+        SqliteDatabase::open(&path)
+    }
+
+    pub fn open(path: &str) -> Result<SqliteDatabase, DatabaseError> {
+        let writer = connect(path)?;
+        // The journal mode is kept by the file, it has to be set before the reader connects
+        writer.pragma_update(None, "journal_mode", "WAL")?;
+        let reader = connect(path)?;
+        Ok(SqliteDatabase {
+            writer: Arc::new(Mutex::new(writer)),
+            reader: Arc::new(Mutex::new(reader)),
+        })
+    }
+
+    ///
+    /// Run statements which modify the database, off the async workers
+    ///
+    pub async fn write<T, F>(&self, f: F) -> Result<T, DatabaseError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, DatabaseError> + Send + 'static,
+    {
+        run(self.writer.clone(), f).await
+    }
+
+    ///
+    /// Run queries, off the async workers
+    ///
+    pub async fn read<T, F>(&self, f: F) -> Result<T, DatabaseError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, DatabaseError> + Send + 'static,
+    {
+        run(self.reader.clone(), f).await
+    }
+}
+
+fn connect(path: &str) -> Result<Connection, DatabaseError> {
+    let connection = Connection::open(path)?;
+    connection.busy_timeout(BUSY_TIMEOUT)?;
+    connection.pragma_update(None, "foreign_keys", "ON")?;
+    Ok(connection)
+}
+
+async fn run<T, F>(connection: Arc<Mutex<Connection>>, f: F) -> Result<T, DatabaseError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut Connection) -> Result<T, DatabaseError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let mut connection = connection.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut connection)
+    })
+    .await?
+}
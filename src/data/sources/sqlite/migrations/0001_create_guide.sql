@@ -0,0 +1,83 @@
+-- Times are stored as seconds since the Unix epoch, in UTC
+CREATE TABLE IF NOT EXISTS CHANNELS (
+    ID INTEGER PRIMARY KEY AUTOINCREMENT,
+    CHANNEL_ID TEXT NOT NULL UNIQUE,
+    DISPLAY_NAME TEXT NOT NULL,
+    ICON TEXT DEFAULT NULL
+);
+
+CREATE TABLE IF NOT EXISTS PROGRAMS (
+    ID INTEGER PRIMARY KEY AUTOINCREMENT,
+    CHANNEL_ID TEXT NOT NULL,
+    START_TIME INTEGER NOT NULL,
+    END_TIME INTEGER NOT NULL,
+    TITLE TEXT NOT NULL,
+    SUBTITLE TEXT DEFAULT NULL,
+    DESCRIPTION TEXT DEFAULT NULL,
+    CATEGORIES TEXT DEFAULT NULL,
+    ICON TEXT DEFAULT NULL,
+    EPISODE_NUM TEXT DEFAULT NULL,
+    RATING_SYSTEM TEXT DEFAULT NULL,
+    RATING_VALUE TEXT DEFAULT NULL,
+    RATING_ICON TEXT DEFAULT NULL,
+    FOREIGN KEY (CHANNEL_ID) REFERENCES CHANNELS(CHANNEL_ID)
+);
+CREATE UNIQUE INDEX IF NOT EXISTS PROGRAMS_CHANNEL_START_TIME ON PROGRAMS (CHANNEL_ID, START_TIME);
+
+CREATE TABLE IF NOT EXISTS CHANNEL_PACKAGES (
+    ID INTEGER PRIMARY KEY AUTOINCREMENT,
+    CHANNEL_ID TEXT NOT NULL,
+    PACKAGE_ID TEXT NOT NULL,
+    FOREIGN KEY (CHANNEL_ID) REFERENCES CHANNELS(CHANNEL_ID)
+);
+CREATE INDEX IF NOT EXISTS CHANNEL_PACKAGES_PACKAGE_ID ON CHANNEL_PACKAGES (PACKAGE_ID);
+
+-- Staging tables filled during an import and published in a single transaction
+CREATE TABLE IF NOT EXISTS CHANNELS_STAGING (
+    CHANNEL_ID TEXT NOT NULL,
+    DISPLAY_NAME TEXT NOT NULL,
+    ICON TEXT DEFAULT NULL
+);
+
+CREATE TABLE IF NOT EXISTS PROGRAMS_STAGING (
+    CHANNEL_ID TEXT NOT NULL,
+    START_TIME INTEGER NOT NULL,
+    END_TIME INTEGER NOT NULL,
+    TITLE TEXT NOT NULL,
+    SUBTITLE TEXT DEFAULT NULL,
+    DESCRIPTION TEXT DEFAULT NULL,
+    CATEGORIES TEXT DEFAULT NULL,
+    ICON TEXT DEFAULT NULL,
+    EPISODE_NUM TEXT DEFAULT NULL,
+    RATING_SYSTEM TEXT DEFAULT NULL,
+    RATING_VALUE TEXT DEFAULT NULL,
+    RATING_ICON TEXT DEFAULT NULL
+);
+CREATE INDEX IF NOT EXISTS PROGRAMS_STAGING_CHANNEL_START_TIME ON PROGRAMS_STAGING (CHANNEL_ID, START_TIME);
+
+CREATE TABLE IF NOT EXISTS CHANNEL_PACKAGES_STAGING (
+    CHANNEL_ID TEXT NOT NULL,
+    PACKAGE_ID TEXT NOT NULL
+);
+
+-- History of the XMLTV imports, STARTED_AT being in milliseconds since the Unix epoch
+CREATE TABLE IF NOT EXISTS IMPORTS (
+    ID INTEGER PRIMARY KEY AUTOINCREMENT,
+    STARTED_AT INTEGER NOT NULL,
+    DURATION_MS INTEGER NOT NULL,
+    SUCCESS INTEGER NOT NULL,
+    PARSED INTEGER NOT NULL,
+    SKIPPED INTEGER NOT NULL,
+    INSERTED INTEGER NOT NULL,
+    ERROR TEXT DEFAULT NULL,
+    REPORT TEXT NOT NULL
+);
+
+-- State of the XMLTV sources of the published guide, used to skip unchanged imports
+CREATE TABLE IF NOT EXISTS SOURCE_STATES (
+    SOURCE TEXT PRIMARY KEY,
+    ETAG TEXT DEFAULT NULL,
+    LAST_MODIFIED TEXT DEFAULT NULL,
+    SHA256 TEXT NOT NULL,
+    UPDATED_AT TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
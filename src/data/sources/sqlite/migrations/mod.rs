@@ -0,0 +1,11 @@
+use crate::data::sources::db::migrations::Migration;
+
+///
+/// Every migration of the SQLite schema, in the order they are applied.
+/// A released migration must never be edited, add a new one instead.
+///
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    name: "create_guide",
+    sql: include_str!("0001_create_guide.sql"),
+}];
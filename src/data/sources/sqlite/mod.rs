@@ -0,0 +1,4 @@
+pub mod database;
+pub mod migrations;
+pub mod sql_queries;
+pub mod sqlite_client;
@@ -0,0 +1,362 @@
+///
+/// Get a page of the channel items from the database
+///
+pub const SELECT_ALL_CHANNELS_QUERY: &str = "\
+SELECT channels.id, channels.channel_id, channels.display_name, channels.icon \
+FROM channels \
+ORDER BY channels.channel_id \
+LIMIT ?1 OFFSET ?2 \
+";
+
+///
+/// Count the channel items of the database
+///
+pub const COUNT_ALL_CHANNELS_QUERY: &str = "SELECT COUNT(*) FROM channels";
+
+///
+/// Get a page of the channel items from the database with a specific package_id
+///
+pub const SELECT_CHANNELS_QUERY: &str = "\
+SELECT channels.id, channels.channel_id, channels.display_name, channels.icon \
+FROM channels \
+JOIN channel_packages ON channels.channel_id = channel_packages.channel_id \
+WHERE channel_packages.package_id = ?1 \
+ORDER BY channels.channel_id \
+LIMIT ?2 OFFSET ?3 \
+";
+
+///
+/// Count the channel items with a specific package_id
+///
+pub const COUNT_CHANNELS_QUERY: &str = "\
+SELECT COUNT(*) FROM channels \
+JOIN channel_packages ON channels.channel_id = channel_packages.channel_id \
+WHERE channel_packages.package_id = ?1 \
+";
+
+///
+/// Get a page of the channel items with the channel ids of a JSON array
+///
+pub const SELECT_CHANNELS_BY_IDS_QUERY: &str = "\
+SELECT channels.id, channels.channel_id, channels.display_name, channels.icon \
+FROM channels \
+WHERE channels.channel_id IN (SELECT value FROM json_each(?1)) \
+ORDER BY channels.channel_id \
+LIMIT ?2 OFFSET ?3 \
+";
+
+///
+/// Count the channel items with the channel ids of a JSON array
+///
+pub const COUNT_CHANNELS_BY_IDS_QUERY: &str = "\
+SELECT COUNT(*) FROM channels \
+WHERE channel_id IN (SELECT value FROM json_each(?1)) \
+";
+
+///
+/// Get a page of the programs of a channel on air during a period,
+/// including those started before it. No end is given as NULL.
+///
+pub const FIND_PROGRAMS_BY_CHANNEL_ID_QUERY: &str = "\
+SELECT * FROM programs WHERE channel_id = ?1 \
+AND end_time > ?2 \
+AND (?3 IS NULL OR start_time < ?3) \
+ORDER BY start_time ASC \
+LIMIT ?4 OFFSET ?5
+";
+
+pub const COUNT_PROGRAMS_BY_CHANNEL_ID_QUERY: &str = "\
+SELECT COUNT(*) FROM programs WHERE channel_id = ?1 \
+AND end_time > ?2 \
+AND (?3 IS NULL OR start_time < ?3) \
+";
+
+///
+/// Get the programs of the channels of a JSON array on air during a period,
+/// including those started before it. No end is given as NULL.
+///
+pub const FIND_PROGRAMS_BY_CHANNEL_IDS_QUERY: &str = "\
+SELECT * FROM programs \
+WHERE channel_id IN (SELECT value FROM json_each(?1)) \
+AND end_time > ?2 \
+AND (?3 IS NULL OR start_time < ?3) \
+ORDER BY channel_id, start_time ASC \
+";
+
+///
+/// Get a page of the channels of a package, of every channel when no package is given,
+/// each joined with its first programs on air at a time or starting after it.
+/// The program columns come first and are NULL for a channel without program,
+/// a single row of NULL channel columns holds the total of an empty page.
+///
+pub const FIND_CHANNELS_WITH_UPCOMING_PROGRAMS_QUERY: &str = "\
+WITH matching AS (\
+SELECT * FROM channels \
+WHERE ?1 IS NULL \
+OR channel_id IN (SELECT channel_id FROM channel_packages WHERE package_id = ?1)), \
+page AS (SELECT * FROM matching ORDER BY channel_id LIMIT ?4 OFFSET ?5), \
+upcoming AS (\
+SELECT * FROM (\
+SELECT *, ROW_NUMBER() OVER (PARTITION BY channel_id ORDER BY start_time) AS position \
+FROM programs \
+WHERE channel_id IN (SELECT channel_id FROM page) AND end_time > ?2) \
+WHERE position <= ?3) \
+SELECT upcoming.id, upcoming.channel_id, upcoming.start_time, upcoming.end_time, upcoming.title, \
+upcoming.subtitle, upcoming.description, upcoming.categories, upcoming.icon, upcoming.episode_num, \
+upcoming.rating_system, upcoming.rating_value, upcoming.rating_icon, \
+page.id, page.channel_id, page.display_name, page.icon, \
+(SELECT COUNT(*) FROM matching) \
+FROM (SELECT 1) AS one \
+LEFT JOIN page ON 1 \
+LEFT JOIN upcoming ON upcoming.channel_id = page.channel_id \
+ORDER BY page.channel_id, upcoming.start_time ASC \
+";
+
+pub const FIND_CURRENT_PROGRAM_BY_CHANNEL_ID_QUERY: &str = "\
+SELECT * FROM programs \
+WHERE channel_id = ?1 \
+AND start_time <= ?2 \
+AND end_time >= ?2 \
+LIMIT 1
+";
+
+///
+/// Get the first programs of a channel starting from a time and lasting at least
+/// a number of seconds
+///
+pub const FIND_TONIGHT_PROGRAMS_BY_CHANNEL_ID_QUERY: &str = "\
+SELECT * FROM programs
+WHERE channel_id = ?1
+AND start_time >= ?2
+AND (end_time - start_time) >= ?3
+ORDER BY start_time ASC
+LIMIT ?4
+";
+
+///
+/// Get a page of the programs matching a pattern.
+/// LIKE ignores the case of ASCII letters in SQLite.
+///
+pub const SEARCH_PROGRAMS_QUERY: &str = "\
+SELECT * FROM programs \
+WHERE title LIKE ?1 OR subtitle LIKE ?1 OR description LIKE ?1 \
+ORDER BY channel_id, start_time \
+LIMIT ?2 OFFSET ?3 \
+";
+
+///
+/// Count the programs matching a pattern
+///
+pub const COUNT_SEARCH_PROGRAMS_QUERY: &str = "\
+SELECT COUNT(*) FROM programs \
+WHERE title LIKE ?1 OR subtitle LIKE ?1 OR description LIKE ?1 \
+";
+
+///
+/// Load a channel into the staging table
+///
+pub const INSERT_STAGING_CHANNEL_QUERY: &str =
+    "INSERT INTO channels_staging (channel_id, display_name, icon) VALUES (?1, ?2, ?3)";
+
+///
+/// Load a channel package into the staging table
+///
+pub const INSERT_STAGING_PACKAGE_QUERY: &str =
+    "INSERT INTO channel_packages_staging (channel_id, package_id) VALUES (?1, ?2)";
+
+///
+/// Load a program into the staging table
+///
+pub const INSERT_STAGING_PROGRAM_QUERY: &str = "\
+INSERT INTO programs_staging (\
+channel_id, start_time, end_time, title, subtitle, description, categories, \
+icon, episode_num, rating_system, rating_value, rating_icon) \
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)";
+
+///
+/// Remove the staged memberships of a package
+///
+pub const DELETE_STAGING_PACKAGE_QUERY: &str =
+    "DELETE FROM channel_packages_staging WHERE package_id = ?1";
+
+///
+/// Remove a staged channel
+///
+pub const DELETE_STAGING_CHANNEL_QUERY: &str = "DELETE FROM channels_staging WHERE channel_id = ?1";
+
+///
+/// Remove the staged programs of a channel
+///
+pub const DELETE_STAGING_PROGRAMS_QUERY: &str =
+    "DELETE FROM programs_staging WHERE channel_id = ?1";
+
+///
+/// Empty the staging tables before a new import
+///
+pub const CLEAR_STAGING_QUERY: &str = "\
+DELETE FROM channels_staging; \
+DELETE FROM channel_packages_staging; \
+DELETE FROM programs_staging;";
+
+///
+/// Insert the new staged channels and update the changed ones, keeping their ids.
+/// The WHERE clause is required by SQLite to tell the upsert from a join.
+///
+pub const PUBLISH_CHANNELS_QUERY: &str = "\
+INSERT INTO channels (channel_id, display_name, icon) \
+SELECT channel_id, display_name, icon \
+FROM channels_staging WHERE true \
+GROUP BY channel_id \
+ON CONFLICT (channel_id) DO UPDATE \
+SET display_name = excluded.display_name, icon = excluded.icon \
+WHERE (channels.display_name, channels.icon) \
+IS NOT (excluded.display_name, excluded.icon) \
+";
+
+///
+/// Remove the live channels which are no longer staged
+///
+pub const DELETE_VANISHED_CHANNELS_QUERY: &str = "\
+DELETE FROM channels \
+WHERE NOT EXISTS (\
+SELECT 1 FROM channels_staging WHERE channels_staging.channel_id = channels.channel_id) \
+";
+
+///
+/// Remove the packages of the live channels
+///
+pub const DELETE_PACKAGES_QUERY: &str = "DELETE FROM channel_packages";
+
+///
+/// Publish the staged channel packages
+///
+pub const PUBLISH_PACKAGES_QUERY: &str = "\
+INSERT INTO channel_packages (channel_id, package_id) \
+SELECT DISTINCT channel_id, package_id \
+FROM channel_packages_staging \
+";
+
+///
+/// Count the staged programs which are not live yet
+///
+pub const COUNT_ADDED_PROGRAMS_QUERY: &str = "\
+SELECT COUNT(*) FROM (SELECT DISTINCT channel_id, start_time FROM programs_staging) staged \
+WHERE NOT EXISTS (\
+SELECT 1 FROM programs \
+WHERE programs.channel_id = staged.channel_id \
+AND programs.start_time = staged.start_time) \
+";
+
+///
+/// Count the live programs whose staged details differ
+///
+pub const COUNT_UPDATED_PROGRAMS_QUERY: &str = "\
+SELECT COUNT(*) FROM (SELECT * FROM programs_staging GROUP BY channel_id, start_time) staged \
+JOIN programs ON programs.channel_id = staged.channel_id \
+AND programs.start_time = staged.start_time \
+WHERE (programs.end_time, programs.title, programs.subtitle, programs.description, \
+programs.categories, programs.icon, programs.episode_num, programs.rating_system, \
+programs.rating_value, programs.rating_icon) \
+IS NOT (staged.end_time, staged.title, staged.subtitle, staged.description, \
+staged.categories, staged.icon, staged.episode_num, staged.rating_system, \
+staged.rating_value, staged.rating_icon) \
+";
+
+///
+/// Insert the new staged programs and update the changed ones, keeping their ids.
+/// A program is identified by its channel and start time.
+///
+pub const PUBLISH_PROGRAMS_QUERY: &str = "\
+INSERT INTO programs (\
+channel_id, start_time, end_time, title, subtitle, description, categories, \
+icon, episode_num, rating_system, rating_value, rating_icon) \
+SELECT channel_id, start_time, end_time, title, subtitle, description, categories, \
+icon, episode_num, rating_system, rating_value, rating_icon \
+FROM programs_staging WHERE true \
+GROUP BY channel_id, start_time \
+ON CONFLICT (channel_id, start_time) DO UPDATE \
+SET end_time = excluded.end_time, title = excluded.title, subtitle = excluded.subtitle, \
+description = excluded.description, categories = excluded.categories, icon = excluded.icon, \
+episode_num = excluded.episode_num, rating_system = excluded.rating_system, \
+rating_value = excluded.rating_value, rating_icon = excluded.rating_icon \
+WHERE (programs.end_time, programs.title, programs.subtitle, programs.description, \
+programs.categories, programs.icon, programs.episode_num, programs.rating_system, \
+programs.rating_value, programs.rating_icon) \
+IS NOT (excluded.end_time, excluded.title, excluded.subtitle, excluded.description, \
+excluded.categories, excluded.icon, excluded.episode_num, excluded.rating_system, \
+excluded.rating_value, excluded.rating_icon) \
+";
+
+///
+/// Remove the live programs which are no longer staged
+///
+pub const DELETE_VANISHED_PROGRAMS_QUERY: &str = "\
+DELETE FROM programs \
+WHERE NOT EXISTS (\
+SELECT 1 FROM programs_staging \
+WHERE programs_staging.channel_id = programs.channel_id \
+AND programs_staging.start_time = programs.start_time) \
+";
+
+///
+/// Save the report of an import
+///
+pub const INSERT_IMPORT_QUERY: &str = "\
+INSERT INTO imports (started_at, duration_ms, success, parsed, skipped, inserted, error, report) \
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) \
+RETURNING id
+";
+
+///
+/// Get the latest import reports
+///
+pub const SELECT_IMPORTS_QUERY: &str = "\
+SELECT id, report FROM imports \
+ORDER BY started_at DESC \
+LIMIT ?1
+";
+
+///
+/// Get the state of the sources of the published guide
+///
+pub const SELECT_SOURCE_STATES_QUERY: &str =
+    "SELECT source, etag, last_modified, sha256 FROM source_states";
+
+///
+/// Delete the state of every source
+///
+pub const DELETE_SOURCE_STATES_QUERY: &str = "DELETE FROM source_states";
+
+///
+/// Save the state of a source
+///
+pub const INSERT_SOURCE_STATE_QUERY: &str =
+    "INSERT INTO source_states (source, etag, last_modified, sha256) VALUES (?1, ?2, ?3, ?4)";
+
+///
+/// Create the table recording the applied migrations
+///
+pub const CREATE_SCHEMA_MIGRATIONS_QUERY: &str = "\
+CREATE TABLE IF NOT EXISTS schema_migrations ( \
+    version INTEGER PRIMARY KEY, \
+    name TEXT NOT NULL, \
+    applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP \
+)";
+
+///
+/// Check whether the migrations table exists, without creating it
+///
+pub const SCHEMA_MIGRATIONS_EXISTS_QUERY: &str =
+    "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'";
+
+///
+/// Get the versions of the applied migrations
+///
+pub const SELECT_SCHEMA_MIGRATIONS_QUERY: &str =
+    "SELECT version FROM schema_migrations ORDER BY version";
+
+///
+/// Record an applied migration
+///
+pub const INSERT_SCHEMA_MIGRATION_QUERY: &str =
+    "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)";
@@ -0,0 +1,875 @@
+use crate::data::converters::{channel_converter, program_converter};
+use crate::data::errors::DatabaseError;
+use crate::data::sources::db::migrations::{self, Migration};
+use crate::data::sources::sqlite::database::SqliteDatabase;
+use crate::data::sources::sqlite::migrations::MIGRATIONS;
+use crate::data::sources::sqlite::sql_queries::{
+    CLEAR_STAGING_QUERY, COUNT_ADDED_PROGRAMS_QUERY, COUNT_ALL_CHANNELS_QUERY,
+    COUNT_CHANNELS_BY_IDS_QUERY, COUNT_CHANNELS_QUERY, COUNT_PROGRAMS_BY_CHANNEL_ID_QUERY,
+    COUNT_SEARCH_PROGRAMS_QUERY, COUNT_UPDATED_PROGRAMS_QUERY, CREATE_SCHEMA_MIGRATIONS_QUERY,
+    DELETE_PACKAGES_QUERY, DELETE_SOURCE_STATES_QUERY, DELETE_STAGING_CHANNEL_QUERY,
+    DELETE_STAGING_PACKAGE_QUERY, DELETE_STAGING_PROGRAMS_QUERY, DELETE_VANISHED_CHANNELS_QUERY,
+    DELETE_VANISHED_PROGRAMS_QUERY, FIND_CHANNELS_WITH_UPCOMING_PROGRAMS_QUERY,
+    FIND_CURRENT_PROGRAM_BY_CHANNEL_ID_QUERY, FIND_PROGRAMS_BY_CHANNEL_ID_QUERY,
+    FIND_PROGRAMS_BY_CHANNEL_IDS_QUERY, FIND_TONIGHT_PROGRAMS_BY_CHANNEL_ID_QUERY,
+    INSERT_IMPORT_QUERY, INSERT_SCHEMA_MIGRATION_QUERY, INSERT_SOURCE_STATE_QUERY,
+    INSERT_STAGING_CHANNEL_QUERY, INSERT_STAGING_PACKAGE_QUERY, INSERT_STAGING_PROGRAM_QUERY,
+    PUBLISH_CHANNELS_QUERY, PUBLISH_PACKAGES_QUERY, PUBLISH_PROGRAMS_QUERY,
+    SCHEMA_MIGRATIONS_EXISTS_QUERY, SEARCH_PROGRAMS_QUERY, SELECT_ALL_CHANNELS_QUERY,
+    SELECT_CHANNELS_BY_IDS_QUERY, SELECT_CHANNELS_QUERY, SELECT_IMPORTS_QUERY,
+    SELECT_SCHEMA_MIGRATIONS_QUERY, SELECT_SOURCE_STATES_QUERY,
+};
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::import_report::{GuideChanges, ImportReport};
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::entities::program::Program;
+use crate::domain::entities::source_state::SourceState;
+use crate::domain::entities::time_window::TimeWindow;
+use chrono::{DateTime, TimeDelta, Utc};
+use rusqlite::{Connection, OptionalExtension, Row, TransactionBehavior, params};
+
+///
+/// Apply the pending migrations in a single transaction, so that a failing
+/// one leaves the schema untouched
+///
+pub async fn run_migrations(database: &SqliteDatabase) -> Result<(), DatabaseError> {
+    println!("Migrating database schema...");
+    database
+        .write(|connection| {
+            // Taking the write lock first keeps other processes from migrating at the same time
+            let transaction =
+                connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            transaction.execute_batch(CREATE_SCHEMA_MIGRATIONS_QUERY)?;
+            let applied = applied_migrations(&transaction)?;
+            if let Some(latest) = applied.last()
+                && MIGRATIONS.iter().all(|m| m.version < *latest)
+            {
+                eprintln!(
+                    "The database schema is at version {}, newer than this application knows about.",
+                    latest
+                );
+            }
+            for migration in migrations::pending(MIGRATIONS, &applied) {
+                println!(
+                    "Applying migration {} {}...",
+                    migration.version, migration.name
+                );
+                transaction.execute_batch(migration.sql)?;
+                transaction.execute(
+                    INSERT_SCHEMA_MIGRATION_QUERY,
+                    params![migration.version, migration.name],
+                )?;
+            }
+            transaction.commit()?;
+            Ok(())
+        })
+        .await?;
+    println!("Database schema is up to date.");
+    Ok(())
+}
+
+///
+/// Get the migrations which are not applied yet, without touching the database
+///
+pub async fn find_pending_migrations(
+    database: &SqliteDatabase,
+) -> Result<Vec<&'static Migration>, DatabaseError> {
+    database
+        .read(|connection| {
+            let exists: bool =
+                connection.query_row(SCHEMA_MIGRATIONS_EXISTS_QUERY, [], |row| row.get(0))?;
+            let applied = if exists {
+                applied_migrations(connection)?
+            } else {
+                vec![]
+            };
+            Ok(migrations::pending(MIGRATIONS, &applied))
+        })
+        .await
+}
+
+fn applied_migrations(connection: &Connection) -> Result<Vec<i32>, DatabaseError> {
+    let mut statement = connection.prepare(SELECT_SCHEMA_MIGRATIONS_QUERY)?;
+    let versions = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<i32>, rusqlite::Error>>()?;
+    Ok(versions)
+}
+
+///
+/// Empty the staging tables before loading a new guide
+///
+pub async fn clear_staging(database: &SqliteDatabase) -> Result<(), DatabaseError> {
+    println!("Clearing staging tables...");
+    database
+        .write(|connection| Ok(connection.execute_batch(CLEAR_STAGING_QUERY)?))
+        .await
+}
+
+///
+/// Remove what was staged for a package which could not be imported entirely,
+/// `channel_ids` being the channels first declared by this package
+///
+pub async fn discard_staged_package(
+    database: &SqliteDatabase,
+    package: String,
+    channel_ids: Vec<String>,
+) -> Result<(), DatabaseError> {
+    println!("Discarding staged package {}...", package);
+    database
+        .write(move |connection| {
+            let transaction = connection.transaction()?;
+            transaction.execute(DELETE_STAGING_PACKAGE_QUERY, [&package])?;
+            {
+                let mut delete_channel = transaction.prepare(DELETE_STAGING_CHANNEL_QUERY)?;
+                let mut delete_programs = transaction.prepare(DELETE_STAGING_PROGRAMS_QUERY)?;
+                for channel_id in &channel_ids {
+                    delete_channel.execute([channel_id])?;
+                    delete_programs.execute([channel_id])?;
+                }
+            }
+            Ok(transaction.commit()?)
+        })
+        .await
+}
+
+///
+/// Make the live channels, packages and programs match the staged ones
+/// in a single transaction, along with the state of the sources they were
+/// read from. Only the changed rows are written, so ids survive refreshes.
+/// On failure the previous guide is kept.
+///
+pub async fn publish_staging(
+    database: &SqliteDatabase,
+    source_states: Vec<SourceState>,
+) -> Result<GuideChanges, DatabaseError> {
+    println!("Publishing staged guide...");
+    let changes = database
+        .write(move |connection| {
+            let transaction = connection.transaction()?;
+            transaction.execute(PUBLISH_CHANNELS_QUERY, [])?;
+            // SQLite does not tell inserted rows from updated ones, count them beforehand
+            let added: i64 =
+                transaction.query_row(COUNT_ADDED_PROGRAMS_QUERY, [], |row| row.get(0))?;
+            let updated: i64 =
+                transaction.query_row(COUNT_UPDATED_PROGRAMS_QUERY, [], |row| row.get(0))?;
+            transaction.execute(PUBLISH_PROGRAMS_QUERY, [])?;
+            let deleted = transaction.execute(DELETE_VANISHED_PROGRAMS_QUERY, [])?;
+            transaction.execute(DELETE_PACKAGES_QUERY, [])?;
+            transaction.execute(PUBLISH_PACKAGES_QUERY, [])?;
+            transaction.execute(DELETE_VANISHED_CHANNELS_QUERY, [])?;
+            transaction.execute(DELETE_SOURCE_STATES_QUERY, [])?;
+            for state in &source_states {
+                transaction.execute(
+                    INSERT_SOURCE_STATE_QUERY,
+                    params![state.source, state.etag, state.last_modified, state.sha256],
+                )?;
+            }
+            transaction.execute_batch(CLEAR_STAGING_QUERY)?;
+            transaction.commit()?;
+            Ok(GuideChanges {
+                added: added as usize,
+                updated: updated as usize,
+                deleted,
+            })
+        })
+        .await?;
+    println!(
+        "Staged guide published: {} programs added, {} updated, {} deleted.",
+        changes.added, changes.updated, changes.deleted
+    );
+    Ok(changes)
+}
+
+pub async fn stage_channels(
+    database: &SqliteDatabase,
+    channels: Vec<Channel>,
+) -> Result<(), DatabaseError> {
+    database
+        .write(move |connection| {
+            let transaction = connection.transaction()?;
+            {
+                let mut insert = transaction.prepare(INSERT_STAGING_CHANNEL_QUERY)?;
+                for channel in &channels {
+                    insert.execute(params![channel.channel_id, channel.name, channel.icon_url])?;
+                }
+            }
+            Ok(transaction.commit()?)
+        })
+        .await
+}
+
+pub async fn stage_channel_packages(
+    database: &SqliteDatabase,
+    channels: Vec<Channel>,
+    package: String,
+) -> Result<(), DatabaseError> {
+    database
+        .write(move |connection| {
+            let transaction = connection.transaction()?;
+            {
+                let mut insert = transaction.prepare(INSERT_STAGING_PACKAGE_QUERY)?;
+                for channel in &channels {
+                    insert.execute(params![channel.channel_id, package])?;
+                }
+            }
+            Ok(transaction.commit()?)
+        })
+        .await
+}
+
+pub async fn stage_programs(
+    database: &SqliteDatabase,
+    programs: Vec<Program>,
+) -> Result<(), DatabaseError> {
+    println!(
+        "Bulk inserting {} programs to the database...",
+        programs.len()
+    );
+    database
+        .write(move |connection| {
+            let transaction = connection.transaction()?;
+            {
+                let mut insert = transaction.prepare(INSERT_STAGING_PROGRAM_QUERY)?;
+                for program in &programs {
+                    // Missing values are stored as empty strings, as in PostgreSQL
+                    let categories = program.categories.as_deref().unwrap_or_default().join(",");
+                    let rating = program.rating.as_ref();
+                    insert.execute(params![
+                        program.channel_id,
+                        program.start_time.timestamp(),
+                        program.end_time.timestamp(),
+                        program.title,
+                        program.sub_title.as_deref().unwrap_or(""),
+                        program.description.as_deref().unwrap_or(""),
+                        categories,
+                        program.icon_url.as_deref().unwrap_or(""),
+                        program.episode_num.as_deref().unwrap_or(""),
+                        rating.and_then(|r| r.system.as_deref()).unwrap_or(""),
+                        rating.and_then(|r| r.value.as_deref()).unwrap_or(""),
+                        rating.and_then(|r| r.icon.as_deref()).unwrap_or(""),
+                    ])?;
+                }
+            }
+            transaction.commit()?;
+            println!("Bulk insert completed.");
+            Ok(())
+        })
+        .await
+}
+
+pub async fn find_all_channels(
+    database: &SqliteDatabase,
+    page: PageRequest,
+) -> Result<Paged<Channel>, DatabaseError> {
+    database
+        .read(move |connection| {
+            Ok(Paged {
+                items: query_channels(
+                    connection,
+                    SELECT_ALL_CHANNELS_QUERY,
+                    [page.limit(), page.offset()],
+                )?,
+                total: count(connection, COUNT_ALL_CHANNELS_QUERY, [])?,
+            })
+        })
+        .await
+}
+
+pub async fn find_channels_by_package(
+    database: &SqliteDatabase,
+    package: String,
+    page: PageRequest,
+) -> Result<Paged<Channel>, DatabaseError> {
+    database
+        .read(move |connection| {
+            Ok(Paged {
+                items: query_channels(
+                    connection,
+                    SELECT_CHANNELS_QUERY,
+                    params![package, page.limit(), page.offset()],
+                )?,
+                total: count(connection, COUNT_CHANNELS_QUERY, [package])?,
+            })
+        })
+        .await
+}
+
+pub async fn find_channels_by_ids(
+    database: &SqliteDatabase,
+    channel_ids: Vec<String>,
+    page: PageRequest,
+) -> Result<Paged<Channel>, DatabaseError> {
+    let channel_ids = json_array(&channel_ids);
+    database
+        .read(move |connection| {
+            Ok(Paged {
+                items: query_channels(
+                    connection,
+                    SELECT_CHANNELS_BY_IDS_QUERY,
+                    params![channel_ids, page.limit(), page.offset()],
+                )?,
+                total: count(connection, COUNT_CHANNELS_BY_IDS_QUERY, [channel_ids])?,
+            })
+        })
+        .await
+}
+
+///
+/// Values bound at once as a JSON array, read back with `json_each`
+///
+fn json_array(values: &[String]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn query_channels(
+    connection: &Connection,
+    query: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Channel>, DatabaseError> {
+    let mut statement = connection.prepare(query)?;
+    let channels = statement
+        .query_map(params, |row| {
+            Ok(Channel {
+                id: row.get(0)?,
+                channel_id: row.get(1)?,
+                name: row.get(2)?,
+                icon_url: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<Channel>, rusqlite::Error>>()?;
+    Ok(channels)
+}
+
+fn query_programs(
+    connection: &Connection,
+    query: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Program>, DatabaseError> {
+    let mut statement = connection.prepare(query)?;
+    let programs = statement
+        .query_map(params, program_row)?
+        .collect::<Result<Vec<Program>, rusqlite::Error>>()?;
+    Ok(programs)
+}
+
+fn query_program(
+    connection: &Connection,
+    query: &str,
+    params: impl rusqlite::Params,
+) -> Result<Option<Program>, DatabaseError> {
+    Ok(connection
+        .query_row(query, params, program_row)
+        .optional()?)
+}
+
+fn program_row(row: &Row) -> rusqlite::Result<Program> {
+    program_converter::sqlite_row_to_entity(row)
+}
+
+fn count(
+    connection: &Connection,
+    query: &str,
+    params: impl rusqlite::Params,
+) -> Result<u64, DatabaseError> {
+    let count: i64 = connection.query_row(query, params, |row| row.get(0))?;
+    Ok(count as u64)
+}
+
+pub async fn find_programs_by_channel_id(
+    database: &SqliteDatabase,
+    channel_id: String,
+    window: TimeWindow,
+    page: PageRequest,
+) -> Result<Paged<Program>, DatabaseError> {
+    let from = window.from.timestamp();
+    let to = window.to.map(|to| to.timestamp());
+    database
+        .read(move |connection| {
+            Ok(Paged {
+                items: query_programs(
+                    connection,
+                    FIND_PROGRAMS_BY_CHANNEL_ID_QUERY,
+                    params![channel_id, from, to, page.limit(), page.offset()],
+                )?,
+                total: count(
+                    connection,
+                    COUNT_PROGRAMS_BY_CHANNEL_ID_QUERY,
+                    params![channel_id, from, to],
+                )?,
+            })
+        })
+        .await
+}
+
+pub async fn find_programs_by_channel_ids(
+    database: &SqliteDatabase,
+    channel_ids: Vec<String>,
+    window: TimeWindow,
+) -> Result<Vec<Program>, DatabaseError> {
+    let channel_ids = json_array(&channel_ids);
+    let from = window.from.timestamp();
+    let to = window.to.map(|to| to.timestamp());
+    database
+        .read(move |connection| {
+            query_programs(
+                connection,
+                FIND_PROGRAMS_BY_CHANNEL_IDS_QUERY,
+                params![channel_ids, from, to],
+            )
+        })
+        .await
+}
+
+pub async fn find_channels_with_upcoming_programs(
+    database: &SqliteDatabase,
+    package: Option<String>,
+    at: DateTime<Utc>,
+    count: u32,
+    page: PageRequest,
+) -> Result<Paged<(Channel, Vec<Program>)>, DatabaseError> {
+    let at = at.timestamp();
+    database
+        .read(move |connection| {
+            let mut statement = connection.prepare(FIND_CHANNELS_WITH_UPCOMING_PROGRAMS_QUERY)?;
+            let mut rows =
+                statement.query(params![package, at, count, page.limit(), page.offset()])?;
+            let mut channels = Vec::new();
+            let mut total: i64 = 0;
+            while let Some(row) = rows.next()? {
+                total = row.get(17)?;
+                if row.get::<_, Option<i32>>(13)?.is_none() {
+                    continue;
+                }
+                let channel = Channel {
+                    id: row.get(13)?,
+                    channel_id: row.get(14)?,
+                    name: row.get(15)?,
+                    icon_url: row.get(16)?,
+                };
+                let program = match row.get::<_, Option<i32>>(0)? {
+                    Some(_) => Some(program_row(row)?),
+                    None => None,
+                };
+                channels.push((channel, program));
+            }
+            Ok(channel_converter::rows_to_channels_with_programs(
+                channels,
+                total as u64,
+            ))
+        })
+        .await
+}
+
+pub async fn find_current_program_by_channel_id(
+    database: &SqliteDatabase,
+    channel_id: String,
+) -> Result<Option<Program>, DatabaseError> {
+    let now = Utc::now().timestamp();
+    database
+        .read(move |connection| {
+            query_program(
+                connection,
+                FIND_CURRENT_PROGRAM_BY_CHANNEL_ID_QUERY,
+                params![channel_id, now],
+            )
+        })
+        .await
+}
+
+pub async fn find_tonight_programs_by_channel_id(
+    database: &SqliteDatabase,
+    channel_id: String,
+    from: DateTime<Utc>,
+    min_duration: TimeDelta,
+    count: u32,
+) -> Result<Vec<Program>, DatabaseError> {
+    let from = from.timestamp();
+    let min_duration = min_duration.num_seconds();
+    database
+        .read(move |connection| {
+            query_programs(
+                connection,
+                FIND_TONIGHT_PROGRAMS_BY_CHANNEL_ID_QUERY,
+                params![channel_id, from, min_duration, count],
+            )
+        })
+        .await
+}
+
+pub async fn search_programs(
+    database: &SqliteDatabase,
+    query_string: String,
+    page: PageRequest,
+) -> Result<Paged<Program>, DatabaseError> {
+    let query = format!("%{}%", query_string);
+    database
+        .read(move |connection| {
+            Ok(Paged {
+                items: query_programs(
+                    connection,
+                    SEARCH_PROGRAMS_QUERY,
+                    params![query, page.limit(), page.offset()],
+                )?,
+                total: count(connection, COUNT_SEARCH_PROGRAMS_QUERY, [query])?,
+            })
+        })
+        .await
+}
+
+///
+/// Save the report of an import and return its id
+///
+pub async fn save_import(
+    database: &SqliteDatabase,
+    report: ImportReport,
+) -> Result<i32, DatabaseError> {
+    database
+        .write(move |connection| {
+            let json = serde_json::to_string(&report).unwrap_or_default();
+            let id = connection.query_row(
+                INSERT_IMPORT_QUERY,
+                params![
+                    report.started_at.timestamp_millis(),
+                    report.duration_ms as i64,
+                    report.succeeded(),
+                    report.parsed as i64,
+                    report.skipped as i64,
+                    report.inserted as i64,
+                    report.error,
+                    json,
+                ],
+                |row| row.get(0),
+            )?;
+            Ok(id)
+        })
+        .await
+}
+
+pub async fn find_imports(
+    database: &SqliteDatabase,
+    limit: i64,
+) -> Result<Vec<ImportReport>, DatabaseError> {
+    database
+        .read(move |connection| {
+            let mut statement = connection.prepare(SELECT_IMPORTS_QUERY)?;
+            let rows = statement
+                .query_map([limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<(i32, String)>, rusqlite::Error>>()?;
+            Ok(rows
+                .into_iter()
+                .filter_map(|(id, report)| {
+                    serde_json::from_str(&report)
+                        .map(|report| ImportReport { id, ..report })
+                        .map_err(|e| eprintln!("Unable to read import report: {}", e))
+                        .ok()
+                })
+                .collect())
+        })
+        .await
+}
+
+///
+/// Get the state of the sources the published guide was read from
+///
+pub async fn find_source_states(
+    database: &SqliteDatabase,
+) -> Result<Vec<SourceState>, DatabaseError> {
+    database
+        .read(|connection| {
+            let mut statement = connection.prepare(SELECT_SOURCE_STATES_QUERY)?;
+            let states = statement
+                .query_map([], |row| {
+                    Ok(SourceState {
+                        source: row.get(0)?,
+                        etag: row.get(1)?,
+                        last_modified: row.get(2)?,
+                        sha256: row.get(3)?,
+                    })
+                })?
+                .collect::<Result<Vec<SourceState>, rusqlite::Error>>()?;
+            Ok(states)
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::test_fixtures::{channel, program, time};
+    use chrono::{FixedOffset, TimeDelta};
+
+    async fn publish(
+        database: &SqliteDatabase,
+        channels: Vec<Channel>,
+        programs: Vec<Program>,
+    ) -> GuideChanges {
+        clear_staging(database).await.unwrap();
+        stage_channels(database, channels.clone()).await.unwrap();
+        stage_channel_packages(database, channels, "ALL".to_string())
+            .await
+            .unwrap();
+        stage_programs(database, programs).await.unwrap();
+        publish_staging(database, vec![]).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_publish_and_query_guide() {
+        // GIVEN
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("guide.db");
+        let database = SqliteDatabase::open(path.to_str().unwrap()).unwrap();
+        run_migrations(&database).await.unwrap();
+        // XMLTV times are given to the second
+        let now = DateTime::from_timestamp(Utc::now().timestamp(), 0)
+            .unwrap()
+            .with_timezone(&FixedOffset::east_opt(3600).unwrap());
+        let on_air = now - TimeDelta::minutes(30);
+        let next = on_air + TimeDelta::hours(1);
+        let later = next + TimeDelta::hours(1);
+        let channels = vec![channel("TF1.fr")];
+        let news = Program {
+            sub_title: Some("It's back".to_string()),
+            categories: Some(vec!["Movie".to_string(), "Drama".to_string()]),
+            ..program("TF1.fr", on_air, "News")
+        };
+        publish(
+            &database,
+            channels.clone(),
+            vec![
+                news.clone(),
+                program("TF1.fr", next, "Movie"),
+                program("TF1.fr", later, "Late show"),
+            ],
+        )
+        .await;
+
+        // WHEN
+        let changes = publish(
+            &database,
+            channels,
+            vec![news, program("TF1.fr", next, "Another movie")],
+        )
+        .await;
+
+        // THEN
+        assert_eq!(
+            changes,
+            GuideChanges {
+                added: 0,
+                updated: 1,
+                deleted: 1
+            }
+        );
+        let current = find_current_program_by_channel_id(&database, "TF1.fr".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.title, "News");
+        assert_eq!(current.start_time, on_air.with_timezone(&Utc));
+        assert_eq!(
+            current.categories,
+            Some(vec!["Movie".to_string(), "Drama".to_string()])
+        );
+        // Missing values are given back as empty strings
+        assert_eq!(current.description, Some(String::new()));
+        assert_eq!(current.icon_url, Some(String::new()));
+        let first_page = PageRequest { page: 0, size: 1 };
+        let until_next = TimeWindow {
+            from: now.with_timezone(&Utc),
+            to: Some(next.with_timezone(&Utc)),
+        };
+        let running =
+            find_programs_by_channel_id(&database, "TF1.fr".to_string(), until_next, first_page)
+                .await
+                .unwrap();
+        assert_eq!(running.total, 1);
+        assert_eq!(running.items[0].title, "News");
+        let from_next = TimeWindow {
+            from: next.with_timezone(&Utc),
+            to: None,
+        };
+        let upcoming =
+            find_programs_by_channel_id(&database, "TF1.fr".to_string(), from_next, first_page)
+                .await
+                .unwrap();
+        assert_eq!(upcoming.total, 1);
+        assert_eq!(upcoming.items[0].title, "Another movie");
+        let found = search_programs(&database, "another".to_string(), first_page)
+            .await
+            .unwrap();
+        assert_eq!(found.total, 1);
+        let second_page = PageRequest { page: 1, size: 1 };
+        let channels = find_channels_by_package(&database, "ALL".to_string(), second_page)
+            .await
+            .unwrap();
+        assert_eq!(channels.total, 1);
+        assert!(channels.items.is_empty());
+        let tonight = find_tonight_programs_by_channel_id(
+            &database,
+            "TF1.fr".to_string(),
+            on_air.with_timezone(&Utc),
+            TimeDelta::minutes(30),
+            2,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            tonight
+                .iter()
+                .map(|p| p.title.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["News", "Another movie"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_programs_of_several_channels() {
+        // GIVEN
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("guide.db");
+        let database = SqliteDatabase::open(path.to_str().unwrap()).unwrap();
+        run_migrations(&database).await.unwrap();
+        let start = time(20, 0);
+        publish(
+            &database,
+            vec![channel("TF1.fr"), channel("France2.fr"), channel("M6.fr")],
+            vec![
+                program("TF1.fr", start, "News"),
+                program("France2.fr", start + TimeDelta::hours(1), "Movie"),
+                program("France2.fr", start, "Weather"),
+                program("M6.fr", start, "Cooking"),
+            ],
+        )
+        .await;
+        let channel_ids = vec![
+            "TF1.fr".to_string(),
+            "France2.fr".to_string(),
+            "Unknown.fr".to_string(),
+        ];
+        let window = TimeWindow {
+            from: start.with_timezone(&Utc),
+            to: Some((start + TimeDelta::hours(2)).with_timezone(&Utc)),
+        };
+        let page = PageRequest { page: 0, size: 10 };
+
+        // WHEN
+        let channels = find_channels_by_ids(&database, channel_ids.clone(), page)
+            .await
+            .unwrap();
+        let programs = find_programs_by_channel_ids(&database, channel_ids, window)
+            .await
+            .unwrap();
+
+        // THEN
+        assert_eq!(channels.total, 2);
+        assert_eq!(channels.items[0].channel_id, "France2.fr");
+        assert_eq!(
+            programs
+                .iter()
+                .map(|p| p.title.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["Weather", "Movie", "News"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_channels_with_upcoming_programs() {
+        // GIVEN
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("guide.db");
+        let database = SqliteDatabase::open(path.to_str().unwrap()).unwrap();
+        run_migrations(&database).await.unwrap();
+        let start = time(20, 0);
+        publish(
+            &database,
+            vec![
+                channel("TF1.fr"),
+                channel("France2.fr"),
+                channel("M6.fr"),
+                channel("Arte.fr"),
+            ],
+            vec![
+                program("TF1.fr", start, "News"),
+                program("France2.fr", start + TimeDelta::hours(1), "Movie"),
+                program("France2.fr", start, "Weather"),
+                program("M6.fr", start - TimeDelta::hours(2), "Cartoons"),
+                program("M6.fr", start, "Cooking"),
+            ],
+        )
+        .await;
+        let at = (start + TimeDelta::minutes(30)).with_timezone(&Utc);
+
+        // WHEN
+        let first = find_channels_with_upcoming_programs(
+            &database,
+            Some("ALL".to_string()),
+            at,
+            1,
+            PageRequest { page: 0, size: 3 },
+        )
+        .await
+        .unwrap();
+        let beyond = find_channels_with_upcoming_programs(
+            &database,
+            None,
+            at,
+            1,
+            PageRequest { page: 5, size: 3 },
+        )
+        .await
+        .unwrap();
+        let unknown = find_channels_with_upcoming_programs(
+            &database,
+            Some("Unknown".to_string()),
+            at,
+            1,
+            PageRequest { page: 0, size: 3 },
+        )
+        .await
+        .unwrap();
+
+        // THEN
+        assert_eq!(first.total, 4);
+        assert_eq!(
+            first
+                .items
+                .iter()
+                .map(|(channel, programs)| (
+                    channel.channel_id.as_str(),
+                    programs
+                        .iter()
+                        .map(|p| p.title.as_str())
+                        .collect::<Vec<&str>>()
+                ))
+                .collect::<Vec<(&str, Vec<&str>)>>(),
+            vec![
+                ("Arte.fr", vec![]),
+                ("France2.fr", vec!["Weather"]),
+                ("M6.fr", vec!["Cooking"]),
+            ]
+        );
+        assert!(beyond.items.is_empty());
+        assert_eq!(beyond.total, 4);
+        assert!(unknown.items.is_empty());
+        assert_eq!(unknown.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pending_migrations() {
+        // GIVEN
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("guide.db");
+        let database = SqliteDatabase::open(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            find_pending_migrations(&database).await.unwrap().len(),
+            MIGRATIONS.len()
+        );
+
+        // WHEN
+        run_migrations(&database).await.unwrap();
+
+        // THEN
+        assert!(find_pending_migrations(&database).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,625 @@
+pub mod parser;
+
+use crate::data::errors::IngestionError;
+use crate::data::sources::xmltv::parser::ItemHandler;
+use crate::data::sources::{api, file};
+use crate::domain::entities::source_state::SourceState;
+use dotenv::var;
+use flate2::read::GzDecoder;
+use liblzma::read::XzDecoder;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+use zip::ZipArchive;
+
+///
+/// Where the XMLTV data of a package comes from
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum XmlTvSource {
+    /// A `.zip`, `.gz`, `.xz` or `.xml` file downloaded over HTTP
+    Url(String),
+    /// A local `.zip`, `.gz`, `.xz` or `.xml` file, or a directory of them
+    File(PathBuf),
+}
+
+///
+/// XMLTV data ready to be read from the local file system
+///
+pub enum XmlTvPayload {
+    /// A local file or directory
+    File(PathBuf),
+    /// A temporary copy of a remote file, deleted once dropped
+    Download {
+        file: NamedTempFile,
+        url: String,
+        content_type: Option<String>,
+    },
+}
+
+///
+/// The outcome of fetching a source
+///
+pub enum XmlTvFetch {
+    /// The server answered that the source did not change
+    NotModified,
+    /// The data is available locally, along with the state to remember
+    Fetched {
+        payload: XmlTvPayload,
+        state: SourceState,
+    },
+}
+
+impl XmlTvFetch {
+    ///
+    /// Whether the content differs from the one of the `previous` import
+    ///
+    pub fn changed(&self, previous: Option<&SourceState>) -> bool {
+        match (self, previous) {
+            (XmlTvFetch::NotModified, _) => false,
+            (XmlTvFetch::Fetched { state, .. }, Some(previous)) => state.sha256 != previous.sha256,
+            (XmlTvFetch::Fetched { .. }, None) => true,
+        }
+    }
+}
+
+///
+/// The encodings of XMLTV data
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XmlTvFormat {
+    Zip,
+    Gzip,
+    Xz,
+    Xml,
+}
+
+impl XmlTvFormat {
+    ///
+    /// Detect the format from the first bytes of the data, then from the
+    /// content type announced by the server, then from the file name.
+    /// Anything else is considered plain XML.
+    ///
+    pub fn detect(magic: &[u8], content_type: Option<&str>, name: &str) -> XmlTvFormat {
+        XmlTvFormat::from_magic(magic)
+            .or_else(|| content_type.and_then(XmlTvFormat::from_content_type))
+            .or_else(|| XmlTvFormat::from_name(name))
+            .unwrap_or(XmlTvFormat::Xml)
+    }
+
+    fn from_magic(magic: &[u8]) -> Option<XmlTvFormat> {
+        if magic.starts_with(b"PK\x03\x04") {
+            Some(XmlTvFormat::Zip)
+        } else if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(XmlTvFormat::Gzip)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(XmlTvFormat::Xz)
+        } else {
+            None
+        }
+    }
+
+    fn from_content_type(content_type: &str) -> Option<XmlTvFormat> {
+        let mime = content_type.split(';').next()?.trim().to_lowercase();
+        match mime.as_str() {
+            "application/zip" | "application/x-zip-compressed" => Some(XmlTvFormat::Zip),
+            "application/gzip" | "application/x-gzip" => Some(XmlTvFormat::Gzip),
+            "application/x-xz" => Some(XmlTvFormat::Xz),
+            "application/xml" | "text/xml" => Some(XmlTvFormat::Xml),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<XmlTvFormat> {
+        let name = name.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(XmlTvFormat::Zip)
+        } else if name.ends_with(".gz") {
+            Some(XmlTvFormat::Gzip)
+        } else if name.ends_with(".xz") {
+            Some(XmlTvFormat::Xz)
+        } else if name.ends_with(".xml") {
+            Some(XmlTvFormat::Xml)
+        } else {
+            None
+        }
+    }
+}
+
+impl XmlTvSource {
+    ///
+    /// `http://` and `https://` locations are downloaded,
+    /// anything else is read from the local file system
+    ///
+    pub fn parse(location: &str) -> XmlTvSource {
+        let location = location.trim();
+        if location.starts_with("http://") || location.starts_with("https://") {
+            return XmlTvSource::Url(location.to_string());
+        }
+        let path = location.strip_prefix("file://").unwrap_or(location);
+        XmlTvSource::File(PathBuf::from(path))
+    }
+
+    ///
+    /// Make the data available locally. Remote files are streamed
+    /// to a temporary file rather than kept in memory, and only
+    /// downloaded if they changed since the `previous` import.
+    ///
+    pub async fn fetch(
+        &self,
+        previous: Option<&SourceState>,
+    ) -> Result<XmlTvFetch, IngestionError> {
+        match self {
+            XmlTvSource::Url(url) => {
+                let download = api::download(
+                    url,
+                    previous.and_then(|p| p.etag.as_deref()),
+                    previous.and_then(|p| p.last_modified.as_deref()),
+                )
+                .await?;
+                let Some(download) = download else {
+                    return Ok(XmlTvFetch::NotModified);
+                };
+                Ok(XmlTvFetch::Fetched {
+                    state: SourceState {
+                        source: self.to_string(),
+                        etag: download.etag,
+                        last_modified: download.last_modified,
+                        sha256: download.sha256,
+                    },
+                    payload: XmlTvPayload::Download {
+                        file: download.file,
+                        url: url.clone(),
+                        content_type: download.content_type,
+                    },
+                })
+            }
+            XmlTvSource::File(path) => {
+                if !path.exists() {
+                    return Err(IngestionError::Fetch(format!(
+                        "{} does not exist",
+                        path.display()
+                    )));
+                }
+                let hashed_path = path.clone();
+                let sha256 = tokio::task::spawn_blocking(move || file::sha256(&hashed_path))
+                    .await
+                    .map_err(|e| IngestionError::Fetch(e.to_string()))??;
+                Ok(XmlTvFetch::Fetched {
+                    state: SourceState {
+                        source: self.to_string(),
+                        etag: None,
+                        last_modified: None,
+                        sha256,
+                    },
+                    payload: XmlTvPayload::File(path.clone()),
+                })
+            }
+        }
+    }
+}
+
+impl XmlTvSource {
+    ///
+    /// Fetch the source, falling back to each mirror in turn when it fails.
+    /// The state of a mirror is remembered as the state of the source.
+    ///
+    pub async fn fetch_with_mirrors(
+        &self,
+        mirrors: &[XmlTvSource],
+        previous: Option<&SourceState>,
+    ) -> Result<XmlTvFetch, IngestionError> {
+        let mut result = self.fetch(previous).await;
+        for mirror in mirrors {
+            let Err(e) = &result else {
+                break;
+            };
+            eprintln!("Failed to fetch {}: {}, trying mirror {}", self, e, mirror);
+            result = mirror.fetch(previous).await.map(|fetch| match fetch {
+                XmlTvFetch::Fetched { payload, state } => XmlTvFetch::Fetched {
+                    payload,
+                    state: SourceState {
+                        source: self.to_string(),
+                        ..state
+                    },
+                },
+                not_modified => not_modified,
+            });
+        }
+        result
+    }
+}
+
+impl Display for XmlTvSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XmlTvSource::Url(url) => write!(f, "{}", url),
+            XmlTvSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+///
+/// The base URL or directory of the XMLTV files
+///
+pub fn base_location() -> Result<String, IngestionError> {
+    let base_location = var("XMLTV_BASE_URL").map_err(|_| {
+        IngestionError::Config("XMLTV_BASE_URL must be set in the environment variables".into())
+    })?;
+    if base_location.ends_with("/") {
+        return Ok(base_location);
+    }
+    Ok(base_location + "/")
+}
+
+///
+/// The mirrors of the base location, from the comma separated `XMLTV_MIRROR_URLS`
+///
+pub fn mirror_locations() -> Vec<String> {
+    var("XMLTV_MIRROR_URLS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|location| location.trim())
+        .filter(|location| !location.is_empty())
+        .map(|location| {
+            if location.ends_with('/') {
+                location.to_string()
+            } else {
+                format!("{}/", location)
+            }
+        })
+        .collect()
+}
+
+pub fn is_supported_file(name: &str) -> bool {
+    XmlTvFormat::from_name(name).is_some()
+}
+
+///
+/// Stream every element of the payload to `handler`.
+/// Every file of a directory is read in turn.
+/// `entry` is the file to read in zip archives, the first XML file by default.
+///
+pub fn read(
+    payload: &XmlTvPayload,
+    entry: Option<&str>,
+    handler: &mut ItemHandler,
+) -> Result<(), IngestionError> {
+    let max_bytes = api::HttpSettings::from_env().max_payload_bytes;
+    match payload {
+        XmlTvPayload::File(path) if path.is_dir() => {
+            for file in file::supported_files(path)? {
+                read_file(
+                    &file,
+                    &file.to_string_lossy(),
+                    None,
+                    entry,
+                    max_bytes,
+                    handler,
+                )?;
+            }
+            Ok(())
+        }
+        XmlTvPayload::File(path) => read_file(
+            path,
+            &path.to_string_lossy(),
+            None,
+            entry,
+            max_bytes,
+            handler,
+        ),
+        XmlTvPayload::Download {
+            file,
+            url,
+            content_type,
+        } => read_file(
+            file.path(),
+            url,
+            content_type.as_deref(),
+            entry,
+            max_bytes,
+            handler,
+        ),
+    }
+}
+
+///
+/// A reader failing once more than `max_bytes` have been read from it,
+/// so that a small archive cannot expand without limit
+///
+struct LimitedReader<R> {
+    inner: R,
+    max_bytes: u64,
+    read: u64,
+}
+
+impl<R: Read> LimitedReader<R> {
+    fn new(inner: R, max_bytes: u64) -> LimitedReader<R> {
+        LimitedReader {
+            inner,
+            max_bytes,
+            read: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // One byte more than allowed tells whether the limit is exceeded
+        let allowed = self
+            .max_bytes
+            .saturating_sub(self.read)
+            .saturating_add(1)
+            .min(buf.len() as u64) as usize;
+        let length = self.inner.read(&mut buf[..allowed])?;
+        self.read += length as u64;
+        if self.read > self.max_bytes {
+            return Err(io::Error::other(format!(
+                "decompressed payload larger than {} bytes",
+                self.max_bytes
+            )));
+        }
+        Ok(length)
+    }
+}
+
+fn read_file(
+    path: &Path,
+    name: &str,
+    content_type: Option<&str>,
+    entry: Option<&str>,
+    max_bytes: u64,
+    handler: &mut ItemHandler,
+) -> Result<(), IngestionError> {
+    println!("Reading XML TV from {}", name);
+    let open_error = |e: std::io::Error| IngestionError::Fetch(format!("{}: {}", name, e));
+    let mut magic = [0u8; 6];
+    let length = File::open(path)
+        .and_then(|mut file| file.read(&mut magic))
+        .map_err(open_error)?;
+    let format = XmlTvFormat::detect(&magic[..length], content_type, name);
+    println!("Detected {:?} format", format);
+
+    let file = File::open(path).map_err(open_error)?;
+    match format {
+        XmlTvFormat::Zip => read_zip(file, entry, max_bytes, handler),
+        XmlTvFormat::Gzip => parser::parse(
+            BufReader::new(LimitedReader::new(GzDecoder::new(file), max_bytes)),
+            handler,
+        ),
+        XmlTvFormat::Xz => parser::parse(
+            BufReader::new(LimitedReader::new(XzDecoder::new(file), max_bytes)),
+            handler,
+        ),
+        XmlTvFormat::Xml => parser::parse(BufReader::new(file), handler),
+    }
+}
+
+fn read_zip(
+    file: File,
+    entry: Option<&str>,
+    max_bytes: u64,
+    handler: &mut ItemHandler,
+) -> Result<(), IngestionError> {
+    let mut archive = match ZipArchive::new(file) {
+        Ok(arch) => arch,
+        Err(e) => {
+            eprintln!("Failed to read zip archive: {}", e);
+            return Err(IngestionError::Archive(e.to_string()));
+        }
+    };
+
+    let name = match entry {
+        Some(entry) => entry.to_string(),
+        None => archive
+            .file_names()
+            .find(|name| name.ends_with(".xml"))
+            .map(|name| name.to_string())
+            .ok_or_else(|| {
+                IngestionError::Archive("No XML file found in the archive.".to_string())
+            })?,
+    };
+    println!("Extracting {} from zip file...", name);
+    let file = match archive.by_name(&name) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Failed to access file in zip archive: {}", e);
+            return Err(IngestionError::Archive(format!("{}: {}", name, e)));
+        }
+    };
+    parser::parse(BufReader::new(LimitedReader::new(file, max_bytes)), handler)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use std::ops::ControlFlow;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tv>
+    <channel id="TF1.fr"><display-name>TF1</display-name></channel>
+    <programme start="20240101080000 +0000" stop="20240101090000 +0000" channel="TF1.fr">
+        <title lang="fr">Le journal</title>
+    </programme>
+</tv>"#;
+
+    fn count_items(payload: &XmlTvPayload, entry: Option<&str>) -> usize {
+        let mut count = 0;
+        read(payload, entry, &mut |item| {
+            assert!(item.is_ok());
+            count += 1;
+            Ok(ControlFlow::Continue(()))
+        })
+        .expect("Payload should be readable");
+        count
+    }
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!(
+            XmlTvSource::parse("https://xmltvfr.fr/xmltv/xmltv.zip"),
+            XmlTvSource::Url("https://xmltvfr.fr/xmltv/xmltv.zip".to_string())
+        );
+        assert_eq!(
+            XmlTvSource::parse("file:///data/xmltv.xml.gz"),
+            XmlTvSource::File(PathBuf::from("/data/xmltv.xml.gz"))
+        );
+        assert_eq!(
+            XmlTvSource::parse("/data/output/"),
+            XmlTvSource::File(PathBuf::from("/data/output/"))
+        );
+    }
+
+    #[test]
+    fn test_read_plain_xml() {
+        // GIVEN
+        let mut file = tempfile::Builder::new().suffix(".xml").tempfile().unwrap();
+        file.write_all(SAMPLE.as_bytes()).unwrap();
+
+        // WHEN
+        let count = count_items(&XmlTvPayload::File(file.path().to_path_buf()), None);
+
+        // THEN
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_read_gzip() {
+        // GIVEN
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(SAMPLE.as_bytes()).unwrap();
+        let mut file = tempfile::Builder::new()
+            .suffix(".xml.gz")
+            .tempfile()
+            .unwrap();
+        file.write_all(&encoder.finish().unwrap()).unwrap();
+
+        // WHEN
+        let count = count_items(&XmlTvPayload::File(file.path().to_path_buf()), None);
+
+        // THEN
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_read_gzip_larger_than_the_limit() {
+        // GIVEN
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(SAMPLE.as_bytes()).unwrap();
+        let mut file = tempfile::Builder::new()
+            .suffix(".xml.gz")
+            .tempfile()
+            .unwrap();
+        file.write_all(&encoder.finish().unwrap()).unwrap();
+        let max_bytes = SAMPLE.len() as u64 - 1;
+
+        // WHEN
+        let result = read_file(
+            file.path(),
+            "guide.xml.gz",
+            None,
+            None,
+            max_bytes,
+            &mut |_| Ok(ControlFlow::Continue(())),
+        );
+
+        // THEN
+        let error = result
+            .expect_err("The payload should be too large")
+            .to_string();
+        assert!(error.contains("larger than"), "{}", error);
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            XmlTvFormat::detect(&[0x1f, 0x8b, 0x08], None, "guide.zip"),
+            XmlTvFormat::Gzip
+        );
+        assert_eq!(
+            XmlTvFormat::detect(b"<?xml", Some("application/x-xz"), "guide"),
+            XmlTvFormat::Xz
+        );
+        assert_eq!(
+            XmlTvFormat::detect(b"", Some("application/zip; charset=binary"), "guide"),
+            XmlTvFormat::Zip
+        );
+        assert_eq!(
+            XmlTvFormat::detect(b"", None, "guide.xml.gz"),
+            XmlTvFormat::Gzip
+        );
+        assert_eq!(
+            XmlTvFormat::detect(b"<tv>", None, "guide"),
+            XmlTvFormat::Xml
+        );
+    }
+
+    #[test]
+    fn test_read_xz_without_extension() {
+        // GIVEN
+        let mut encoder = liblzma::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(SAMPLE.as_bytes()).unwrap();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&encoder.finish().unwrap()).unwrap();
+
+        // WHEN
+        let count = count_items(&XmlTvPayload::File(file.path().to_path_buf()), None);
+
+        // THEN
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_read_zip_entry() {
+        // GIVEN
+        let file = tempfile::Builder::new().suffix(".zip").tempfile().unwrap();
+        let mut zip = ZipWriter::new(file.reopen().unwrap());
+        zip.start_file("empty.xml", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"<tv></tv>").unwrap();
+        zip.start_file("guide.xml", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(SAMPLE.as_bytes()).unwrap();
+        zip.finish().unwrap();
+        let payload = XmlTvPayload::File(file.path().to_path_buf());
+
+        // WHEN
+        let first = count_items(&payload, None);
+        let chosen = count_items(&payload, Some("guide.xml"));
+
+        // THEN
+        assert_eq!(first, 0);
+        assert_eq!(chosen, 2);
+    }
+
+    #[test]
+    fn test_fetch_changed() {
+        // GIVEN
+        let previous = SourceState {
+            source: "/data/xmltv.zip".to_string(),
+            etag: None,
+            last_modified: None,
+            sha256: "abc".to_string(),
+        };
+        let fetched = |sha256: &str| XmlTvFetch::Fetched {
+            payload: XmlTvPayload::File(PathBuf::from("/data/xmltv.zip")),
+            state: SourceState {
+                sha256: sha256.to_string(),
+                ..previous.clone()
+            },
+        };
+
+        // THEN
+        assert!(!XmlTvFetch::NotModified.changed(Some(&previous)));
+        assert!(!fetched("abc").changed(Some(&previous)));
+        assert!(fetched("def").changed(Some(&previous)));
+        assert!(fetched("abc").changed(None));
+    }
+}
@@ -0,0 +1,266 @@
+use crate::data::errors::IngestionError;
+use crate::data::models::{Channel, Program};
+use quick_xml::Reader;
+use quick_xml::Writer;
+use quick_xml::XmlVersion;
+use quick_xml::events::{BytesStart, Event};
+use std::io::BufRead;
+use std::ops::ControlFlow;
+
+///
+/// A top level element of a XMLTV document
+///
+pub enum XmlTvItem {
+    Channel(Channel),
+    Program(Box<Program>),
+}
+
+///
+/// Receives each element of the document, or the error raised by a malformed one.
+/// Returning `ControlFlow::Break` stops the reading of the document.
+///
+pub type ItemHandler<'a> =
+    dyn FnMut(Result<XmlTvItem, IngestionError>) -> Result<ControlFlow<()>, IngestionError> + 'a;
+
+#[derive(Clone, Copy)]
+enum ItemKind {
+    Channel,
+    Program,
+}
+
+///
+/// Walk the `<channel>` and `<programme>` elements of a XMLTV document one at a time,
+/// so that only a single element is held in memory whatever the size of the guide.
+///
+pub fn parse<R: BufRead>(reader: R, handler: &mut ItemHandler) -> Result<(), IngestionError> {
+    let mut reader = Reader::from_reader(reader);
+    let mut buf = Vec::new();
+    let mut depth = 0usize;
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| parse_error(&reader, e))?;
+        let item = match event {
+            Event::Start(start) if depth == 1 => match item_kind(&start) {
+                Some(kind) => {
+                    let start = start.into_owned();
+                    let key = item_key(&start, kind);
+                    let fragment = capture_element(&mut reader, start)?;
+                    Some(to_item(kind, key, fragment))
+                }
+                None => {
+                    depth += 1;
+                    None
+                }
+            },
+            Event::Empty(start) if depth == 1 => item_kind(&start).map(|kind| {
+                let key = item_key(&start, kind);
+                let fragment = write_events(vec![Event::Empty(start)]);
+                to_item(kind, key, fragment)
+            }),
+            Event::Start(_) => {
+                depth += 1;
+                None
+            }
+            Event::End(_) => {
+                depth = depth.saturating_sub(1);
+                None
+            }
+            Event::Eof => return Ok(()),
+            _ => None,
+        };
+        buf.clear();
+
+        if let Some(item) = item
+            && handler(item)?.is_break()
+        {
+            return Ok(());
+        }
+    }
+}
+
+fn item_kind(start: &BytesStart) -> Option<ItemKind> {
+    match start.name().into_inner() {
+        "channel" => Some(ItemKind::Channel),
+        "programme" => Some(ItemKind::Program),
+        _ => None,
+    }
+}
+
+///
+/// The channel id of the element, used to report malformed elements
+///
+fn item_key(start: &BytesStart, kind: ItemKind) -> String {
+    let attribute = match kind {
+        ItemKind::Channel => "id",
+        ItemKind::Program => "channel",
+    };
+    start
+        .try_get_attribute(attribute)
+        .ok()
+        .flatten()
+        .and_then(|a| {
+            a.normalized_value(XmlVersion::Implicit1_0)
+                .ok()
+                .map(|v| v.into_owned())
+        })
+        .unwrap_or_default()
+}
+
+///
+/// Read the events up to the end of the element and serialize them back
+///
+fn capture_element<R: BufRead>(
+    reader: &mut Reader<R>,
+    start: BytesStart<'static>,
+) -> Result<Result<String, String>, IngestionError> {
+    let mut events = vec![Event::Start(start)];
+    let mut buf = Vec::new();
+    let mut depth = 1usize;
+    while depth > 0 {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| parse_error(reader, e))?;
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            Event::Eof => {
+                return Err(IngestionError::Parse(
+                    "Unexpected end of document".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        events.push(event.into_owned());
+        buf.clear();
+    }
+    Ok(write_events(events))
+}
+
+fn write_events(events: Vec<Event>) -> Result<String, String> {
+    let mut writer = Writer::new(Vec::new());
+    for event in events {
+        writer.write_event(event).map_err(|e| e.to_string())?;
+    }
+    String::from_utf8(writer.into_inner()).map_err(|e| e.to_string())
+}
+
+fn to_item(
+    kind: ItemKind,
+    key: String,
+    fragment: Result<String, String>,
+) -> Result<XmlTvItem, IngestionError> {
+    let result = fragment.and_then(|fragment| match kind {
+        ItemKind::Channel => serde_xml_rs::from_str::<Channel>(&fragment)
+            .map(XmlTvItem::Channel)
+            .map_err(|e| e.to_string()),
+        ItemKind::Program => serde_xml_rs::from_str::<Program>(&fragment)
+            .map(|p| XmlTvItem::Program(Box::new(p)))
+            .map_err(|e| e.to_string()),
+    });
+    result.map_err(|reason| match kind {
+        ItemKind::Channel => IngestionError::InvalidChannel {
+            channel_id: key,
+            reason,
+        },
+        ItemKind::Program => IngestionError::InvalidProgram {
+            channel_id: key,
+            reason,
+        },
+    })
+}
+
+fn parse_error<R>(reader: &Reader<R>, e: quick_xml::Error) -> IngestionError {
+    IngestionError::Parse(format!("{} at position {}", e, reader.error_position()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE tv SYSTEM "xmltv.dtd">
+<tv generator-info-name="test">
+    <channel id="TF1.fr">
+        <display-name>TF1</display-name>
+        <icon src="https://example.com/tf1.png?a=1&amp;b=2"/>
+    </channel>
+    <!-- comment -->
+    <programme start="20240101080000 +0000" stop="20240101090000 +0000" channel="TF1.fr">
+        <title lang="fr">Tom &amp; Jerry</title>
+        <desc lang="fr">Un chat et une souris</desc>
+    </programme>
+    <programme start="20240101090000 +0000" channel="TF1.fr">
+        <title lang="fr">Missing stop</title>
+    </programme>
+</tv>"#;
+
+    fn collect(content: &str) -> Vec<Result<XmlTvItem, IngestionError>> {
+        let mut items = vec![];
+        parse(content.as_bytes(), &mut |item| {
+            items.push(item);
+            Ok(ControlFlow::Continue(()))
+        })
+        .expect("Document should be readable");
+        items
+    }
+
+    #[test]
+    fn test_parse_items() {
+        // WHEN
+        let items = collect(SAMPLE);
+
+        // THEN
+        assert_eq!(items.len(), 3);
+        match &items[0] {
+            Ok(XmlTvItem::Channel(channel)) => {
+                assert_eq!(&channel.id, "TF1.fr");
+                assert_eq!(&channel.display_name.content, "TF1");
+                assert_eq!(
+                    &channel.icon.as_ref().unwrap().src,
+                    "https://example.com/tf1.png?a=1&b=2"
+                );
+            }
+            _ => panic!("Expected a channel"),
+        }
+        match &items[1] {
+            Ok(XmlTvItem::Program(program)) => {
+                assert_eq!(&program.title, "Tom & Jerry");
+                assert_eq!(&program.channel, "TF1.fr");
+            }
+            _ => panic!("Expected a programme"),
+        }
+        assert!(matches!(
+            &items[2],
+            Err(IngestionError::InvalidProgram { channel_id, .. }) if channel_id == "TF1.fr"
+        ));
+    }
+
+    #[test]
+    fn test_parse_stops_on_break() {
+        // GIVEN
+        let mut count = 0;
+
+        // WHEN
+        parse(SAMPLE.as_bytes(), &mut |_| {
+            count += 1;
+            Ok(ControlFlow::Break(()))
+        })
+        .expect("Document should be readable");
+
+        // THEN
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_document() {
+        // GIVEN
+        let truncated = &SAMPLE[..SAMPLE.find("<desc").unwrap()];
+
+        // WHEN
+        let result = parse(truncated.as_bytes(), &mut |_| Ok(ControlFlow::Continue(())));
+
+        // THEN
+        assert!(matches!(result, Err(IngestionError::Parse(_))));
+    }
+}
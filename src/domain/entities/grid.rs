@@ -0,0 +1,8 @@
+///
+/// The channels of a grid
+///
+#[derive(Debug, PartialEq)]
+pub enum GridChannels {
+    Package(String),
+    ChannelIds(Vec<String>),
+}
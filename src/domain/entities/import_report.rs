@@ -0,0 +1,238 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Maximum number of warnings kept in a report
+const MAX_WARNINGS: usize = 100;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ImportReport {
+    /// The id of the import
+    pub id: i32,
+
+    /// The start time of the import
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Local>,
+
+    /// The end time of the import
+    #[serde(rename = "finishedAt")]
+    pub finished_at: Option<DateTime<Local>>,
+
+    /// The duration of the import in milliseconds
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u64,
+
+    /// The number of channels and programmes read from the sources
+    pub parsed: usize,
+
+    /// The number of malformed channels and programmes that were skipped
+    pub skipped: usize,
+
+    /// The number of channels and programmes saved to the database
+    pub inserted: usize,
+
+    /// The detail of each package
+    pub packages: Vec<PackageReport>,
+
+    /// Non fatal problems met during the import
+    pub warnings: Vec<String>,
+
+    /// The number of warnings that did not fit in the report
+    #[serde(rename = "omittedWarnings")]
+    pub omitted_warnings: usize,
+
+    /// The error which aborted the import, the previous guide is then kept
+    pub error: Option<String>,
+
+    /// True when no source changed since the previous import, the guide is then kept
+    #[serde(rename = "notModified", default)]
+    pub not_modified: bool,
+
+    /// The programmes changed in the published guide
+    #[serde(default)]
+    pub changes: GuideChanges,
+
+    /// The number of channels and programmes written to the database per second
+    #[serde(rename = "rowsPerSecond", default)]
+    pub rows_per_second: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct GuideChanges {
+    /// The number of programmes added to the guide
+    pub added: usize,
+
+    /// The number of programmes whose details changed
+    pub updated: usize,
+
+    /// The number of programmes no longer in the sources
+    pub deleted: usize,
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct PackageReport {
+    /// The package identifier (e.g. "ALL", "FR", "TNT")
+    pub package: String,
+
+    /// The URL or path the package was read from
+    #[serde(rename = "sourceUrl")]
+    pub source_url: String,
+
+    #[serde(rename = "channelsParsed")]
+    pub channels_parsed: usize,
+
+    #[serde(rename = "channelsSkipped")]
+    pub channels_skipped: usize,
+
+    #[serde(rename = "channelsInserted")]
+    pub channels_inserted: usize,
+
+    /// The number of channels imported by previous packages which joined this one
+    #[serde(rename = "channelsJoined", default)]
+    pub channels_joined: usize,
+
+    #[serde(rename = "programsParsed")]
+    pub programs_parsed: usize,
+
+    #[serde(rename = "programsSkipped")]
+    pub programs_skipped: usize,
+
+    #[serde(rename = "programsInserted")]
+    pub programs_inserted: usize,
+
+    /// The time spent writing to the database in milliseconds
+    #[serde(rename = "stagingMs", default)]
+    pub staging_ms: u64,
+
+    /// The number of channels and programmes written to the database per second
+    #[serde(rename = "rowsPerSecond", default)]
+    pub rows_per_second: u64,
+
+    /// The error which prevented the package from being imported, if any
+    pub error: Option<String>,
+
+    /// True when the source did not change since the previous import
+    #[serde(rename = "notModified", default)]
+    pub not_modified: bool,
+}
+
+impl ImportReport {
+    pub fn new() -> ImportReport {
+        ImportReport {
+            id: 0,
+            started_at: Local::now(),
+            finished_at: None,
+            duration_ms: 0,
+            parsed: 0,
+            skipped: 0,
+            inserted: 0,
+            packages: vec![],
+            warnings: vec![],
+            omitted_warnings: 0,
+            error: None,
+            not_modified: false,
+            changes: GuideChanges::default(),
+            rows_per_second: 0,
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn warn(&mut self, warning: String) {
+        if self.warnings.len() < MAX_WARNINGS {
+            self.warnings.push(warning);
+        } else {
+            self.omitted_warnings += 1;
+        }
+    }
+
+    ///
+    /// Compute the totals and mark the import as finished
+    ///
+    pub fn finish(&mut self) {
+        let finished_at = Local::now();
+        self.duration_ms = (finished_at - self.started_at).num_milliseconds().max(0) as u64;
+        self.finished_at = Some(finished_at);
+        self.parsed = self
+            .packages
+            .iter()
+            .map(|p| p.channels_parsed + p.programs_parsed)
+            .sum();
+        self.skipped = self
+            .packages
+            .iter()
+            .map(|p| p.channels_skipped + p.programs_skipped)
+            .sum();
+        self.inserted = self
+            .packages
+            .iter()
+            .map(|p| p.channels_inserted + p.programs_inserted)
+            .sum();
+        for package in &mut self.packages {
+            package.rows_per_second = throughput(
+                package.channels_inserted + package.programs_inserted,
+                package.staging_ms,
+            );
+        }
+        let staging_ms = self.packages.iter().map(|p| p.staging_ms).sum();
+        self.rows_per_second = throughput(self.inserted, staging_ms);
+    }
+}
+
+impl Default for ImportReport {
+    fn default() -> Self {
+        ImportReport::new()
+    }
+}
+
+impl PackageReport {
+    pub fn new(package: &str, source_url: String) -> PackageReport {
+        PackageReport {
+            package: package.to_string(),
+            source_url,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_staging_time(&mut self, elapsed: Duration) {
+        self.staging_ms += elapsed.as_millis() as u64;
+    }
+}
+
+fn throughput(rows: usize, ms: u64) -> u64 {
+    if ms == 0 {
+        return 0;
+    }
+    rows as u64 * 1000 / ms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finish_computes_totals_and_throughput() {
+        // GIVEN
+        let mut report = ImportReport::new();
+        let mut package = PackageReport::new("ALL", "xmltv.zip".to_string());
+        package.channels_parsed = 10;
+        package.channels_inserted = 10;
+        package.programs_parsed = 1000;
+        package.programs_skipped = 10;
+        package.programs_inserted = 990;
+        package.add_staging_time(Duration::from_millis(500));
+        report.packages.push(package);
+
+        // WHEN
+        report.finish();
+
+        // THEN
+        assert_eq!(report.parsed, 1010);
+        assert_eq!(report.skipped, 10);
+        assert_eq!(report.inserted, 1000);
+        assert_eq!(report.packages[0].rows_per_second, 2000);
+        assert_eq!(report.rows_per_second, 2000);
+    }
+}
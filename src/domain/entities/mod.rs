@@ -1,3 +1,13 @@
 pub mod channel;
+pub mod grid;
+pub mod import_report;
+pub mod package;
+pub mod page;
 pub mod program;
 pub mod rating;
+pub mod refresh_job;
+pub mod refresh_status;
+pub mod source_state;
+#[cfg(test)]
+pub mod test_fixtures;
+pub mod time_window;
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Package {
+    /// The unique identifier of the package (e.g. "TNT")
+    pub id: String,
+
+    /// The name of the package
+    pub name: String,
+}
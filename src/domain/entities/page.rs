@@ -0,0 +1,29 @@
+///
+/// A page of a list requested by a client, the first page being 0
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageRequest {
+    pub page: u32,
+    /// Maximum number of items of the page
+    pub size: u32,
+}
+
+impl PageRequest {
+    /// Number of items of the list before the page
+    pub fn offset(&self) -> i64 {
+        self.page as i64 * self.size as i64
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.size as i64
+    }
+}
+
+///
+/// The items of a page, along with the number of items of the whole list
+///
+#[derive(Debug, PartialEq)]
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+}
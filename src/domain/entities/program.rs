@@ -1,8 +1,8 @@
+use crate::domain::entities::rating::Rating;
 use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
-use crate::domain::entities::rating::Rating;
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Program {
     /// The id of the program
     pub id: i32,
@@ -42,5 +42,4 @@ pub struct Program {
 
     /// The rating of the program
     pub rating: Option<Rating>,
-
 }
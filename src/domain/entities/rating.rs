@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Rating {
     /// The system of the rating (e.g., "MPAA", "TVPG")
     pub system: Option<String>,
@@ -10,4 +10,4 @@ pub struct Rating {
 
     /// The icon associated with the rating, if any
     pub icon: Option<String>,
-}
\ No newline at end of file
+}
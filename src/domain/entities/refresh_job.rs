@@ -0,0 +1,49 @@
+use crate::domain::entities::import_report::ImportReport;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RefreshTrigger {
+    /// Initial import when the server starts
+    Startup,
+    /// Import started by the refresh schedule
+    Schedule,
+    /// Import requested through the admin API
+    Manual,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RefreshJobStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct RefreshJob {
+    /// The unique identifier of the job
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+
+    /// What started the job
+    pub trigger: RefreshTrigger,
+
+    /// The current status of the job
+    pub status: RefreshJobStatus,
+
+    /// The current step of the import
+    pub progress: String,
+
+    /// The start time of the job
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Local>,
+
+    /// The end time of the job
+    #[serde(rename = "finishedAt")]
+    pub finished_at: Option<DateTime<Local>>,
+
+    /// The report of the import once the job is finished
+    pub report: Option<ImportReport>,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct RefreshStatus {
+    /// Human readable description of the refresh schedule
+    pub schedule: String,
+
+    /// Whether a refresh is currently running
+    pub running: bool,
+
+    /// The id of the running refresh job, if any
+    #[serde(rename = "currentJobId")]
+    pub current_job_id: Option<String>,
+
+    /// The start time of the last refresh, successful or not
+    #[serde(rename = "lastRunAt")]
+    pub last_run_at: Option<DateTime<Local>>,
+
+    /// The end time of the last successful refresh
+    #[serde(rename = "lastSuccessAt")]
+    pub last_success_at: Option<DateTime<Local>>,
+
+    /// The end time of the last failed refresh
+    #[serde(rename = "lastFailureAt")]
+    pub last_failure_at: Option<DateTime<Local>>,
+
+    /// The time of the next scheduled refresh, if any
+    #[serde(rename = "nextRunAt")]
+    pub next_run_at: Option<DateTime<Local>>,
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+///
+/// What was known of a XMLTV source when it was last imported
+///
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SourceState {
+    /// The URL or path of the source
+    pub source: String,
+
+    /// The ETag header returned by the server
+    pub etag: Option<String>,
+
+    /// The Last-Modified header returned by the server
+    #[serde(rename = "lastModified")]
+    pub last_modified: Option<String>,
+
+    /// The SHA-256 of the payload, hex encoded
+    pub sha256: String,
+}
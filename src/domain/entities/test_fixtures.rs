@@ -0,0 +1,41 @@
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::program::Program;
+use chrono::{DateTime, FixedOffset, TimeDelta, TimeZone};
+
+pub fn channel(channel_id: &str) -> Channel {
+    Channel {
+        id: 0,
+        channel_id: channel_id.to_string(),
+        name: channel_id.to_string(),
+        icon_url: String::new(),
+    }
+}
+
+///
+/// A time of the day of the tests, 14 July 2024 in Paris
+///
+pub fn time(hour: u32, minute: u32) -> DateTime<FixedOffset> {
+    FixedOffset::east_opt(7200)
+        .unwrap()
+        .with_ymd_and_hms(2024, 7, 14, hour, minute, 0)
+        .unwrap()
+}
+
+///
+/// A programme of an hour, without any optional value
+///
+pub fn program(channel_id: &str, start_time: DateTime<FixedOffset>, title: &str) -> Program {
+    Program {
+        id: 0,
+        channel_id: channel_id.to_string(),
+        start_time,
+        end_time: start_time + TimeDelta::hours(1),
+        title: title.to_string(),
+        sub_title: None,
+        description: None,
+        categories: None,
+        icon_url: None,
+        episode_num: None,
+        rating: None,
+    }
+}
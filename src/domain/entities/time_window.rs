@@ -0,0 +1,94 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+
+///
+/// The period during which the programmes of a list are on air
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeWindow {
+    /// The programmes ended by then are left out
+    pub from: DateTime<Utc>,
+    /// The programmes starting from then are left out, none for no end
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl TimeWindow {
+    /// The programmes on air on a calendar day of a timezone
+    pub fn day<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> TimeWindow {
+        TimeWindow {
+            from: start_of_day(date, tz),
+            to: date.succ_opt().map(|next_day| start_of_day(next_day, tz)),
+        }
+    }
+}
+
+fn start_of_day<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> DateTime<Utc> {
+    at_local_time(date, NaiveTime::MIN, tz)
+}
+
+///
+/// The instant of a local date and time in a timezone, the earliest one when the time
+/// happens twice, and an hour later when it is skipped by a change of offset
+///
+pub fn at_local_time<Tz: TimeZone>(date: NaiveDate, time: NaiveTime, tz: &Tz) -> DateTime<Utc> {
+    let local = date.and_time(time);
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(local + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .map(|instant| instant.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day_in_timezone() {
+        // GIVEN
+        let date = NaiveDate::from_ymd_opt(2024, 7, 14).unwrap();
+
+        // WHEN
+        let window = TimeWindow::day(date, &chrono_tz::Europe::Paris);
+
+        // THEN
+        assert_eq!(
+            window.from,
+            Utc.with_ymd_and_hms(2024, 7, 13, 22, 0, 0).unwrap()
+        );
+        assert_eq!(
+            window.to,
+            Some(Utc.with_ymd_and_hms(2024, 7, 14, 22, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_day_of_a_change_of_offset() {
+        // GIVEN
+        let date = NaiveDate::from_ymd_opt(2024, 10, 27).unwrap();
+
+        // WHEN
+        let window = TimeWindow::day(date, &chrono_tz::Europe::Paris);
+
+        // THEN
+        assert_eq!(window.to.unwrap() - window.from, TimeDelta::hours(25));
+    }
+
+    #[test]
+    fn test_skipped_midnight() {
+        // GIVEN
+        // Chile moved its clocks from midnight to 1:00 on this day
+        let date = NaiveDate::from_ymd_opt(2022, 9, 11).unwrap();
+
+        // WHEN
+        let window = TimeWindow::day(date, &chrono_tz::America::Santiago);
+
+        // THEN
+        assert_eq!(
+            window.from,
+            Utc.with_ymd_and_hms(2022, 9, 11, 4, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,23 @@
+use std::fmt::{Display, Formatter};
+
+///
+/// Errors raised by the repositories, whatever the storage backend
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryError {
+    /// The storage cannot be reached for now, the operation may be retried later
+    Unavailable(String),
+    /// The storage could not run the operation
+    Failed(String),
+}
+
+impl Display for RepositoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepositoryError::Unavailable(e) => write!(f, "Storage unavailable: {}", e),
+            RepositoryError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
@@ -0,0 +1,137 @@
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::import_report::{GuideChanges, ImportReport};
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::entities::program::Program;
+use crate::domain::entities::source_state::SourceState;
+use crate::domain::entities::time_window::TimeWindow;
+use crate::domain::errors::RepositoryError;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+
+///
+/// Read access to the channels of the published guide
+///
+#[async_trait]
+pub trait ChannelRepository: Send + Sync {
+    /// A page of the channels, by channel id
+    async fn find_all_channels(&self, page: PageRequest)
+    -> Result<Paged<Channel>, RepositoryError>;
+
+    /// A page of the channels of a package, by channel id
+    async fn find_channels_by_package(
+        &self,
+        package: &str,
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError>;
+
+    /// A page of the channels among `channel_ids`, by channel id
+    async fn find_channels_by_ids(
+        &self,
+        channel_ids: &[String],
+        page: PageRequest,
+    ) -> Result<Paged<Channel>, RepositoryError>;
+}
+
+///
+/// Read access to the programmes of the published guide
+///
+#[async_trait]
+pub trait ProgramRepository: Send + Sync {
+    /// A page of the programmes of a channel on air during `window`, by start time
+    async fn find_programs_by_channel_id(
+        &self,
+        channel_id: &str,
+        window: TimeWindow,
+        page: PageRequest,
+    ) -> Result<Paged<Program>, RepositoryError>;
+
+    /// The programmes of several channels on air during `window`, by channel and start time
+    async fn find_programs_by_channel_ids(
+        &self,
+        channel_ids: &[String],
+        window: TimeWindow,
+    ) -> Result<Vec<Program>, RepositoryError>;
+
+    /// A page of the channels of a package, of every channel when `package` is None, by channel id,
+    /// each with its first `count` programmes on air at `at` or starting after, by start time
+    async fn find_channels_with_upcoming_programs(
+        &self,
+        package: Option<&str>,
+        at: DateTime<Utc>,
+        count: u32,
+        page: PageRequest,
+    ) -> Result<Paged<(Channel, Vec<Program>)>, RepositoryError>;
+
+    /// The programme on air on a channel
+    async fn find_current_program_by_channel_id(
+        &self,
+        channel_id: &str,
+    ) -> Result<Option<Program>, RepositoryError>;
+
+    /// The first `count` programmes of at least `min_duration` starting from `from` on a channel,
+    /// by start time
+    async fn find_tonight_programs_by_channel_id(
+        &self,
+        channel_id: &str,
+        from: DateTime<Utc>,
+        min_duration: TimeDelta,
+        count: u32,
+    ) -> Result<Vec<Program>, RepositoryError>;
+
+    /// A page of the programmes whose title, subtitle or description contains `query`,
+    /// ignoring case, by channel and start time
+    async fn search_programs(
+        &self,
+        query: &str,
+        page: PageRequest,
+    ) -> Result<Paged<Program>, RepositoryError>;
+}
+
+///
+/// History of the XMLTV imports
+///
+#[async_trait]
+pub trait ImportRepository: Send + Sync {
+    /// Save the report of an import and return its id
+    async fn save_import(&self, report: &ImportReport) -> Result<i32, RepositoryError>;
+
+    /// The latest imports first
+    async fn find_imports(&self, limit: i64) -> Result<Vec<ImportReport>, RepositoryError>;
+}
+
+///
+/// Loads a new guide aside and publishes it at once,
+/// so that readers never see part of an import
+///
+#[async_trait]
+pub trait GuideWriter: Send + Sync {
+    /// The state of the sources the published guide was read from
+    async fn find_source_states(&self) -> Result<Vec<SourceState>, RepositoryError>;
+
+    /// Forget what was staged by a previous import
+    async fn clear_staging(&self) -> Result<(), RepositoryError>;
+
+    async fn stage_channels(&self, channels: &[Channel]) -> Result<(), RepositoryError>;
+
+    async fn stage_channel_packages(
+        &self,
+        channels: &[Channel],
+        package: &str,
+    ) -> Result<(), RepositoryError>;
+
+    async fn stage_programs(&self, programs: &[Program]) -> Result<(), RepositoryError>;
+
+    /// Remove what was staged for a package which could not be imported entirely,
+    /// `channel_ids` being the channels first declared by this package
+    async fn discard_staged_package(
+        &self,
+        package: &str,
+        channel_ids: &[String],
+    ) -> Result<(), RepositoryError>;
+
+    /// Replace the published guide with the staged one, keeping the ids of unchanged programmes
+    async fn publish_staging(
+        &self,
+        source_states: &[SourceState],
+    ) -> Result<GuideChanges, RepositoryError>;
+}
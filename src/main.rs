@@ -1,20 +1,65 @@
+mod config;
 mod data;
 mod domain;
 mod presentation;
+mod scheduler;
 
-use crate::data::repositories::xml_tv_repository;
+use crate::config::packages;
+use crate::config::prime_time::PrimeTime;
+use crate::data::errors::DatabaseError;
+use crate::data::repositories::storage::{Storage, StorageBackend};
+use crate::data::sources::db::migrations::Migration;
+use crate::data::sources::db::pool::{self, DbPool, PoolSettings};
 use crate::data::sources::db::postgres_client;
+use crate::data::sources::sqlite::database::SqliteDatabase;
+use crate::data::sources::sqlite::sqlite_client;
+use crate::domain::entities::refresh_job::RefreshTrigger;
 use crate::presentation::routes;
+use crate::presentation::state::AppState;
+use crate::scheduler::{RefreshSchedule, Scheduler};
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
 
-    postgres_client::init_schema();
+    let packages = match packages::load() {
+        Ok(packages) => packages,
+        Err(e) => {
+            eprintln!("Invalid packages configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let print_pending = std::env::args().any(|arg| arg == "--print-pending-migrations");
+    let storage = match StorageBackend::from_env() {
+        StorageBackend::Postgres => Storage::postgres(connect_postgres(print_pending).await),
+        StorageBackend::Sqlite => Storage::sqlite(open_sqlite(print_pending).await),
+        StorageBackend::Memory => {
+            if print_pending {
+                println!("The memory storage backend has no migration.");
+                return;
+            }
+            println!("Storing the guide in memory, it is imported again on restart.");
+            Storage::memory()
+        }
+    };
 
-    xml_tv_repository::init_xml_tv_data().await;
+    let scheduler = Scheduler::default();
+    scheduler
+        .run_refresh(storage.clone(), RefreshTrigger::Startup)
+        .await;
+    scheduler.start(storage.clone(), RefreshSchedule::from_env());
 
-    let router = routes::create_router();
+    let prime_time = PrimeTime::from_env();
+    println!("Prime time: {}", prime_time.describe());
+
+    let router = routes::create_router(AppState {
+        storage,
+        prime_time,
+        packages: Arc::new(packages),
+        scheduler,
+    });
 
     // run our app with hyper, listening globally on port 3000
     let listener = match tokio::net::TcpListener::bind("0.0.0.0:3000").await {
@@ -24,11 +69,72 @@ async fn main() {
         }
         Ok(l) => l,
     };
-    match axum::serve(listener, router).await {
+    if let Err(e) = axum::serve(listener, router).await {
+        eprintln!("Server error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+///
+/// Create the connection pool and bring the schema up to date,
+/// or only list the pending migrations and exit when `print_pending` is set
+///
+async fn connect_postgres(print_pending: bool) -> DbPool {
+    let pool = match pool::create_pool(&PoolSettings::from_env()) {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Invalid database configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if print_pending {
+        print_pending_migrations(postgres_client::find_pending_migrations(&pool).await);
+        std::process::exit(0);
+    }
+    if let Err(e) = postgres_client::run_migrations(&pool).await {
+        eprintln!("Unable to migrate the database schema: {}", e);
+        std::process::exit(1);
+    }
+    pool
+}
+
+///
+/// Open the SQLite database file and bring its schema up to date,
+/// or only list the pending migrations and exit when `print_pending` is set
+///
+async fn open_sqlite(print_pending: bool) -> SqliteDatabase {
+    let database = match SqliteDatabase::from_env() {
+        Ok(database) => database,
+        Err(e) => {
+            eprintln!("Unable to open the SQLite database: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if print_pending {
+        print_pending_migrations(sqlite_client::find_pending_migrations(&database).await);
+        std::process::exit(0);
+    }
+    if let Err(e) = sqlite_client::run_migrations(&database).await {
+        eprintln!("Unable to migrate the database schema: {}", e);
+        std::process::exit(1);
+    }
+    database
+}
+
+///
+/// List the migrations which would be applied at the next startup
+///
+fn print_pending_migrations(pending: Result<Vec<&'static Migration>, DatabaseError>) {
+    match pending {
+        Ok(pending) if pending.is_empty() => println!("No pending migration."),
+        Ok(pending) => {
+            for migration in pending {
+                println!("{:04} {}", migration.version, migration.name);
+            }
+        }
         Err(e) => {
-            eprintln!("Server error: {}", e);
+            eprintln!("Unable to read the applied migrations: {}", e);
             std::process::exit(1);
         }
-        Ok(_) => {}
     }
 }
@@ -1,6 +1,938 @@
-use serde::{Deserialize, Serialize};
+use crate::domain::entities::channel::Channel;
+use crate::domain::entities::grid::GridChannels;
+use crate::domain::entities::page::{PageRequest, Paged};
+use crate::domain::entities::program::Program;
+use crate::domain::entities::time_window::TimeWindow;
+use crate::presentation::errors::FieldError;
+use crate::presentation::extractors::{Validate, require};
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeDelta, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize};
 
+/// Number of items of a page when no size is given
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Maximum number of items of a page
+const MAX_PAGE_SIZE: u32 = 1000;
+
+/// Length of a grid when no end is given
+const DEFAULT_GRID_HOURS: i64 = 3;
+
+/// Maximum length of a grid
+const MAX_GRID_HOURS: i64 = 48;
+
+/// Number of programmes following the one on air when none is given
+const DEFAULT_NEXT_PROGRAMS: u32 = 2;
+
+/// Maximum number of programmes following the one on air
+const MAX_NEXT_PROGRAMS: u32 = 2;
+
+/// Number of days ahead of today of the last evening which may be requested,
+/// the guide covering about a week
+const MAX_TONIGHT_DAYS_AHEAD: i64 = 14;
+
+///
+/// A page of a list, numbered from 0
+///
 #[derive(Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Page<T> {
     pub content: Vec<T>,
-}
\ No newline at end of file
+    pub page: u32,
+    pub size: u32,
+    pub total_elements: u64,
+    pub total_pages: u64,
+    /// The page to request for the following items, none on the last page
+    pub next_page: Option<u32>,
+    /// The page to request for the preceding items, none on the first page
+    pub previous_page: Option<u32>,
+}
+
+impl<T> Page<T> {
+    pub fn new(paged: Paged<T>, request: PageRequest) -> Page<T> {
+        let total_pages = paged.total.div_ceil(request.size as u64);
+        let last_page = u32::try_from(total_pages.saturating_sub(1)).unwrap_or(u32::MAX);
+        Page {
+            content: paged.items,
+            page: request.page,
+            size: request.size,
+            total_elements: paged.total,
+            total_pages,
+            next_page: Some(request.page + 1).filter(|next| (*next as u64) < total_pages),
+            // Beyond the end of the list, the last page precedes
+            previous_page: request
+                .page
+                .checked_sub(1)
+                .map(|previous| previous.min(last_page)),
+        }
+    }
+}
+
+///
+/// Query parameters selecting a page of a list
+///
+#[derive(Deserialize)]
+pub struct PageQuery {
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default = "default_page_size")]
+    pub size: u32,
+}
+
+fn default_page_size() -> u32 {
+    DEFAULT_PAGE_SIZE
+}
+
+impl PageQuery {
+    pub fn request(&self) -> PageRequest {
+        PageRequest {
+            page: self.page,
+            size: self.size,
+        }
+    }
+}
+
+impl Validate for PageQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        if (1..=MAX_PAGE_SIZE).contains(&self.size) {
+            vec![]
+        } else {
+            vec![FieldError::new(
+                "size",
+                &format!("must be between 1 and {}", MAX_PAGE_SIZE),
+            )]
+        }
+    }
+}
+
+///
+/// Query parameters of the requests about the programmes of a channel
+///
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelQuery {
+    #[serde(default)]
+    pub channel_id: String,
+}
+
+impl Validate for ChannelQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = vec![];
+        require("channelId", &self.channel_id, &mut errors);
+        errors
+    }
+}
+
+///
+/// Query parameters selecting the period of the programmes of a channel:
+/// `from` and `to`, or a calendar `date` in the `tz` timezone
+///
+#[derive(Deserialize)]
+pub struct WindowQuery {
+    #[serde(default, deserialize_with = "date_time")]
+    pub from: Option<DateTime<FixedOffset>>,
+    #[serde(default, deserialize_with = "date_time")]
+    pub to: Option<DateTime<FixedOffset>>,
+    pub date: Option<NaiveDate>,
+    /// The timezone of `date`, the one of the guide (`PRIME_TIME_TZ`) by default
+    pub tz: Option<Tz>,
+}
+
+///
+/// RFC 3339 date and time, the `+` of its offset may have been decoded as a space
+///
+fn date_time<'de, D>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&value.replace(' ', "+"))
+        .map(Some)
+        .map_err(|_| {
+            serde::de::Error::custom("must be a RFC 3339 date and time, e.g. 2024-07-14T20:00:00Z")
+        })
+}
+
+impl WindowQuery {
+    ///
+    /// The period requested, from now on when none is given,
+    /// a `date` without `tz` being a day of `guide_tz`
+    ///
+    pub fn window(&self, guide_tz: &Tz) -> TimeWindow {
+        match (self.date, self.tz) {
+            (Some(date), tz) => TimeWindow::day(date, &tz.unwrap_or(*guide_tz)),
+            (None, _) => TimeWindow {
+                from: self
+                    .from
+                    .map(|from| from.with_timezone(&Utc))
+                    .unwrap_or_else(Utc::now),
+                to: self.to.map(|to| to.with_timezone(&Utc)),
+            },
+        }
+    }
+
+    ///
+    /// The period of a grid, which always ends: a few hours from its start by default
+    ///
+    pub fn grid_window(&self, guide_tz: &Tz) -> Result<TimeWindow, FieldError> {
+        let window = self.window(guide_tz);
+        let to = window
+            .to
+            .unwrap_or(window.from + TimeDelta::hours(DEFAULT_GRID_HOURS));
+        if to - window.from > TimeDelta::hours(MAX_GRID_HOURS) {
+            return Err(FieldError::new(
+                "to",
+                &format!("must be at most {} hours after from", MAX_GRID_HOURS),
+            ));
+        }
+        Ok(TimeWindow {
+            from: window.from,
+            to: Some(to),
+        })
+    }
+}
+
+impl Validate for WindowQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = vec![];
+        if self.date.is_some() && (self.from.is_some() || self.to.is_some()) {
+            errors.push(FieldError::new(
+                "date",
+                "cannot be combined with from and to",
+            ));
+        }
+        if self.tz.is_some() && self.date.is_none() {
+            errors.push(FieldError::new("tz", "only applies to date"));
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to)
+            && to <= from
+        {
+            errors.push(FieldError::new("to", "must be after from"));
+        }
+        // Without from, the period starts now
+        if let (None, Some(to)) = (self.from, self.to)
+            && to <= Utc::now()
+        {
+            errors.push(FieldError::new(
+                "to",
+                "must be in the future unless from is given",
+            ));
+        }
+        errors
+    }
+}
+
+///
+/// Query parameters selecting the channels of a grid:
+/// a package, or a comma separated list of channel ids
+///
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridQuery {
+    pub package: Option<String>,
+    pub channel_ids: Option<String>,
+}
+
+impl GridQuery {
+    pub fn channels(&self) -> GridChannels {
+        match &self.package {
+            Some(package) => GridChannels::Package(package.trim().to_string()),
+            None => GridChannels::ChannelIds(self.channel_id_list()),
+        }
+    }
+
+    fn channel_id_list(&self) -> Vec<String> {
+        self.channel_ids
+            .iter()
+            .flat_map(|channel_ids| channel_ids.split(','))
+            .map(str::trim)
+            .filter(|channel_id| !channel_id.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Validate for GridQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = vec![];
+        match (&self.package, &self.channel_ids) {
+            (None, None) => errors.push(FieldError::new(
+                "package",
+                "is required unless channelIds is given",
+            )),
+            (Some(_), Some(_)) => errors.push(FieldError::new(
+                "channelIds",
+                "cannot be combined with package",
+            )),
+            (Some(package), None) => require("package", package, &mut errors),
+            (None, Some(_)) => {
+                if self.channel_id_list().is_empty() {
+                    errors.push(FieldError::new("channelIds", "is required"));
+                }
+            }
+        }
+        errors
+    }
+}
+
+///
+/// A page of channels with their programmes during a period, to be drawn as a timeline
+///
+#[derive(Serialize)]
+pub struct Grid {
+    pub from: DateTime<Utc>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub channels: Page<GridChannel>,
+}
+
+///
+/// A channel of a grid, with its programmes by start time
+///
+#[derive(Serialize)]
+pub struct GridChannel {
+    #[serde(flatten)]
+    pub channel: Channel,
+    pub programs: Vec<GridProgram>,
+}
+
+///
+/// A programme of a grid, with the part of it on air during the period of the grid
+///
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridProgram {
+    #[serde(flatten)]
+    pub program: Program,
+    /// The start time of the programme, or the start of the grid when it started before
+    pub clipped_start_time: DateTime<FixedOffset>,
+    /// The end time of the programme, or the end of the grid when it ends after
+    pub clipped_end_time: DateTime<FixedOffset>,
+}
+
+impl Grid {
+    ///
+    /// Build the grid of a page of channels with their programmes on air during `window`
+    ///
+    pub fn new(
+        channels: Paged<(Channel, Vec<Program>)>,
+        window: TimeWindow,
+        request: PageRequest,
+    ) -> Grid {
+        let rows = Paged {
+            items: channels
+                .items
+                .into_iter()
+                .map(|(channel, programs)| GridChannel {
+                    channel,
+                    programs: programs
+                        .into_iter()
+                        .map(|program| GridProgram::clipped(program, window))
+                        .collect(),
+                })
+                .collect(),
+            total: channels.total,
+        };
+        Grid {
+            from: window.from,
+            to: window.to,
+            channels: Page::new(rows, request),
+        }
+    }
+}
+
+impl GridProgram {
+    pub fn clipped(program: Program, window: TimeWindow) -> GridProgram {
+        let clipped_start_time = program
+            .start_time
+            .max(window.from.with_timezone(program.start_time.offset()));
+        let clipped_end_time = window
+            .to
+            .map(|to| {
+                program
+                    .end_time
+                    .min(to.with_timezone(program.end_time.offset()))
+            })
+            .unwrap_or(program.end_time);
+        GridProgram {
+            program,
+            clipped_start_time,
+            clipped_end_time,
+        }
+    }
+}
+
+///
+/// Query parameters of the programmes of an evening
+///
+#[derive(Deserialize)]
+pub struct TonightQuery {
+    /// The day of the evening, tonight by default
+    pub date: Option<NaiveDate>,
+    #[serde(default)]
+    pub parts: TonightParts,
+}
+
+impl Validate for TonightQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = vec![];
+        // Yesterday is still tonight after midnight, or in a timezone behind UTC
+        let today = Utc::now().date_naive();
+        if let Some(date) = self.date
+            && (date < today - TimeDelta::days(1)
+                || date > today + TimeDelta::days(MAX_TONIGHT_DAYS_AHEAD))
+        {
+            errors.push(FieldError::new(
+                "date",
+                &format!(
+                    "must be between yesterday and {} days from today",
+                    MAX_TONIGHT_DAYS_AHEAD
+                ),
+            ));
+        }
+        errors
+    }
+}
+
+///
+/// The parts of the evening requested
+///
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TonightParts {
+    /// The "première partie de soirée"
+    #[default]
+    First,
+    /// The "première" and "deuxième partie de soirée"
+    Both,
+}
+
+impl TonightParts {
+    /// Number of programmes of the evening to find
+    pub fn count(&self) -> u32 {
+        match self {
+            TonightParts::First => 1,
+            TonightParts::Both => 2,
+        }
+    }
+}
+
+///
+/// The programmes of an evening, the first part alone unless both were requested
+///
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Tonight {
+    FirstPart(Program),
+    #[serde(rename_all = "camelCase")]
+    BothParts {
+        first_part: Program,
+        /// None when nothing of long enough follows the first part
+        second_part: Option<Box<Program>>,
+    },
+}
+
+///
+/// Query parameters of what is on air on the channels of a package
+///
+#[derive(Deserialize)]
+pub struct NowQuery {
+    /// Number of programmes following the one on air
+    #[serde(default = "default_next_programs")]
+    pub next: u32,
+}
+
+fn default_next_programs() -> u32 {
+    DEFAULT_NEXT_PROGRAMS
+}
+
+impl Validate for NowQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.next <= MAX_NEXT_PROGRAMS {
+            vec![]
+        } else {
+            vec![FieldError::new(
+                "next",
+                &format!("must be between 0 and {}", MAX_NEXT_PROGRAMS),
+            )]
+        }
+    }
+}
+
+///
+/// A channel with the programme on air and the following ones
+///
+#[derive(Serialize)]
+pub struct ChannelNow {
+    #[serde(flatten)]
+    pub channel: Channel,
+    /// None when nothing is on air
+    pub current: Option<CurrentProgram>,
+    pub next: Vec<Program>,
+}
+
+impl ChannelNow {
+    ///
+    /// Build a page of channels with the programme on air at `now` and the following ones
+    ///
+    pub fn page(
+        channels: Paged<(Channel, Option<Program>, Vec<Program>)>,
+        now: DateTime<Utc>,
+        request: PageRequest,
+    ) -> Page<ChannelNow> {
+        let rows = Paged {
+            items: channels
+                .items
+                .into_iter()
+                .map(|(channel, current, next)| ChannelNow {
+                    channel,
+                    current: current.map(|program| CurrentProgram::at(program, now)),
+                    next,
+                })
+                .collect(),
+            total: channels.total,
+        };
+        Page::new(rows, request)
+    }
+}
+
+///
+/// The programme on air on a channel, with how much of it has been broadcast
+///
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentProgram {
+    #[serde(flatten)]
+    pub program: Program,
+    /// From 0 when the programme starts to 100 when it ends
+    pub elapsed_percent: u8,
+}
+
+impl CurrentProgram {
+    pub fn at(program: Program, now: DateTime<Utc>) -> CurrentProgram {
+        let duration = (program.end_time - program.start_time).num_seconds();
+        let elapsed = (now - program.start_time.with_timezone(&Utc)).num_seconds();
+        let elapsed_percent = if duration > 0 {
+            (elapsed * 100 / duration).clamp(0, 100) as u8
+        } else {
+            100
+        };
+        CurrentProgram {
+            program,
+            elapsed_percent,
+        }
+    }
+}
+
+///
+/// Body of a programme search
+///
+#[derive(Deserialize)]
+pub struct SearchRequest {
+    #[serde(default)]
+    pub query: String,
+}
+
+impl Validate for SearchRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = vec![];
+        require("query", &self.query, &mut errors);
+        if !query_valid(&self.query) {
+            errors.push(FieldError::new(
+                "query",
+                "only lower case letters and spaces are allowed",
+            ));
+        }
+        errors
+    }
+}
+
+fn query_valid(query: &str) -> bool {
+    // The only allowed characers a lower case letters (ASCII 97-122)
+    query
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_whitespace())
+}
+
+///
+/// Query parameters of the import history
+///
+#[derive(Deserialize)]
+pub struct ImportsQuery {
+    /// Number of imports to return
+    pub limit: Option<i64>,
+}
+
+impl Validate for ImportsQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        match self.limit {
+            Some(limit) if limit < 1 => vec![FieldError::new("limit", "must be positive")],
+            _ => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::test_fixtures::{channel, program, time};
+
+    #[test]
+    fn test_middle_page() {
+        // GIVEN
+        let paged = Paged {
+            items: vec![3, 4, 5],
+            total: 10,
+        };
+        let request = PageRequest { page: 1, size: 3 };
+
+        // WHEN
+        let page = Page::new(paged, request);
+
+        // THEN
+        assert_eq!(page.total_pages, 4);
+        assert_eq!(page.next_page, Some(2));
+        assert_eq!(page.previous_page, Some(0));
+    }
+
+    #[test]
+    fn test_last_page() {
+        // GIVEN
+        let paged = Paged {
+            items: vec![9],
+            total: 10,
+        };
+        let request = PageRequest { page: 3, size: 3 };
+
+        // WHEN
+        let page = Page::new(paged, request);
+
+        // THEN
+        assert_eq!(page.total_pages, 4);
+        assert_eq!(page.next_page, None);
+        assert_eq!(page.previous_page, Some(2));
+    }
+
+    #[test]
+    fn test_beyond_last_page() {
+        // GIVEN
+        let paged: Paged<i32> = Paged {
+            items: vec![],
+            total: 10,
+        };
+        let request = PageRequest { page: 7, size: 3 };
+
+        // WHEN
+        let page = Page::new(paged, request);
+
+        // THEN
+        assert_eq!(page.next_page, None);
+        assert_eq!(page.previous_page, Some(3));
+    }
+
+    #[test]
+    fn test_empty_list() {
+        // GIVEN
+        let paged: Paged<i32> = Paged {
+            items: vec![],
+            total: 0,
+        };
+        let request = PageRequest { page: 0, size: 100 };
+
+        // WHEN
+        let page = Page::new(paged, request);
+
+        // THEN
+        assert_eq!(page.total_pages, 0);
+        assert_eq!(page.next_page, None);
+        assert_eq!(page.previous_page, None);
+    }
+
+    #[test]
+    fn test_missing_channel_id() {
+        // GIVEN
+        let query = ChannelQuery {
+            channel_id: " ".to_string(),
+        };
+
+        // WHEN
+        let errors = query.validate();
+
+        // THEN
+        assert_eq!(errors, vec![FieldError::new("channelId", "is required")]);
+    }
+
+    #[test]
+    fn test_window_between_dates() {
+        // GIVEN
+        let query = WindowQuery {
+            from: DateTime::parse_from_rfc3339("2024-07-14T20:00:00+02:00").ok(),
+            to: DateTime::parse_from_rfc3339("2024-07-14T23:00:00+02:00").ok(),
+            date: None,
+            tz: None,
+        };
+
+        // WHEN
+        let window = query.window(&chrono_tz::Europe::Paris);
+
+        // THEN
+        assert!(query.validate().is_empty());
+        assert_eq!(window.from.to_rfc3339(), "2024-07-14T18:00:00+00:00");
+        assert_eq!(
+            window.to.map(|to| to.to_rfc3339()),
+            Some("2024-07-14T21:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_window_of_a_day_of_the_guide() {
+        // GIVEN
+        let query = WindowQuery {
+            from: None,
+            to: None,
+            date: NaiveDate::from_ymd_opt(2024, 7, 14),
+            tz: None,
+        };
+
+        // WHEN
+        let window = query.window(&chrono_tz::Europe::Paris);
+
+        // THEN
+        assert_eq!(window.from.to_rfc3339(), "2024-07-13T22:00:00+00:00");
+        assert_eq!(
+            window.to.map(|to| to.to_rfc3339()),
+            Some("2024-07-14T22:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_window_conflicting_parameters() {
+        // GIVEN
+        let query = WindowQuery {
+            from: DateTime::parse_from_rfc3339("2024-07-14T20:00:00Z").ok(),
+            to: DateTime::parse_from_rfc3339("2024-07-14T19:00:00Z").ok(),
+            date: NaiveDate::from_ymd_opt(2024, 7, 14),
+            tz: None,
+        };
+
+        // WHEN
+        let errors = query.validate();
+
+        // THEN
+        assert_eq!(
+            errors,
+            vec![
+                FieldError::new("date", "cannot be combined with from and to"),
+                FieldError::new("to", "must be after from"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_window_ending_in_the_past_without_start() {
+        // GIVEN
+        let query = WindowQuery {
+            from: None,
+            to: DateTime::parse_from_rfc3339("2024-07-14T23:00:00+02:00").ok(),
+            date: None,
+            tz: None,
+        };
+
+        // WHEN
+        let errors = query.validate();
+
+        // THEN
+        assert_eq!(
+            errors,
+            vec![FieldError::new(
+                "to",
+                "must be in the future unless from is given"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_grid_window_of_a_few_hours_by_default() {
+        // GIVEN
+        let query = WindowQuery {
+            from: DateTime::parse_from_rfc3339("2024-07-14T20:00:00Z").ok(),
+            to: None,
+            date: None,
+            tz: None,
+        };
+
+        // WHEN
+        let window = query.grid_window(&chrono_tz::Europe::Paris);
+
+        // THEN
+        assert_eq!(
+            window.map(|w| w.to.map(|to| to.to_rfc3339())),
+            Ok(Some("2024-07-14T23:00:00+00:00".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_grid_window_too_long() {
+        // GIVEN
+        let query = WindowQuery {
+            from: DateTime::parse_from_rfc3339("2024-07-14T20:00:00Z").ok(),
+            to: DateTime::parse_from_rfc3339("2024-07-17T20:00:00Z").ok(),
+            date: None,
+            tz: None,
+        };
+
+        // WHEN
+        let window = query.grid_window(&chrono_tz::Europe::Paris);
+
+        // THEN
+        assert_eq!(
+            window,
+            Err(FieldError::new("to", "must be at most 48 hours after from"))
+        );
+    }
+
+    #[test]
+    fn test_grid_channels() {
+        // GIVEN
+        let by_ids = GridQuery {
+            package: None,
+            channel_ids: Some("TF1.fr, France2.fr,".to_string()),
+        };
+        let both = GridQuery {
+            package: Some("ALL".to_string()),
+            channel_ids: Some("TF1.fr".to_string()),
+        };
+        let none = GridQuery {
+            package: None,
+            channel_ids: None,
+        };
+
+        // WHEN
+        let channels = by_ids.channels();
+
+        // THEN
+        assert!(by_ids.validate().is_empty());
+        assert_eq!(
+            channels,
+            GridChannels::ChannelIds(vec!["TF1.fr".to_string(), "France2.fr".to_string()])
+        );
+        assert_eq!(
+            both.validate(),
+            vec![FieldError::new(
+                "channelIds",
+                "cannot be combined with package"
+            )]
+        );
+        assert_eq!(
+            none.validate(),
+            vec![FieldError::new(
+                "package",
+                "is required unless channelIds is given"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_grid_clips_programmes_to_the_window() {
+        // GIVEN
+        let channels = Paged {
+            items: vec![(
+                channel("TF1.fr"),
+                vec![
+                    Program {
+                        end_time: time(21, 0),
+                        ..program("TF1.fr", time(19, 0), "News")
+                    },
+                    Program {
+                        end_time: time(23, 0),
+                        ..program("TF1.fr", time(21, 0), "Movie")
+                    },
+                ],
+            )],
+            total: 3,
+        };
+        let window = TimeWindow {
+            from: time(20, 0).with_timezone(&Utc),
+            to: Some(time(22, 0).with_timezone(&Utc)),
+        };
+        let request = PageRequest { page: 0, size: 1 };
+
+        // WHEN
+        let grid = Grid::new(channels, window, request);
+
+        // THEN
+        assert_eq!(grid.from, window.from);
+        assert_eq!(grid.channels.total_elements, 3);
+        let programs = &grid.channels.content[0].programs;
+        assert_eq!(programs[0].clipped_start_time, time(20, 0));
+        assert_eq!(programs[0].clipped_end_time, time(21, 0));
+        assert_eq!(programs[1].clipped_start_time, time(21, 0));
+        assert_eq!(programs[1].clipped_end_time, time(22, 0));
+        assert_eq!(programs[1].program.end_time, time(23, 0));
+    }
+
+    #[test]
+    fn test_channel_now_elapsed_percent() {
+        // GIVEN
+        let channels = Paged {
+            items: vec![(
+                channel("TF1.fr"),
+                Some(program("TF1.fr", time(20, 0), "News")),
+                vec![program("TF1.fr", time(21, 0), "Movie")],
+            )],
+            total: 1,
+        };
+        let now = time(20, 15).with_timezone(&Utc);
+        let request = PageRequest { page: 0, size: 10 };
+
+        // WHEN
+        let page = ChannelNow::page(channels, now, request);
+
+        // THEN
+        let current = page.content[0].current.as_ref().unwrap();
+        assert_eq!(current.program.title, "News");
+        assert_eq!(current.elapsed_percent, 25);
+        assert_eq!(page.content[0].next[0].title, "Movie");
+    }
+
+    #[test]
+    fn test_tonight_date_range() {
+        // GIVEN
+        let today = Utc::now().date_naive();
+        let query = |date| TonightQuery {
+            date: Some(date),
+            parts: TonightParts::First,
+        };
+
+        // THEN
+        assert!(query(today - TimeDelta::days(1)).validate().is_empty());
+        assert!(query(today + TimeDelta::days(7)).validate().is_empty());
+        assert_eq!(query(today - TimeDelta::days(2)).validate().len(), 1);
+        assert_eq!(query(today + TimeDelta::days(365)).validate().len(), 1);
+    }
+
+    #[test]
+    fn test_search_query_characters() {
+        // GIVEN
+        let valid = SearchRequest {
+            query: "journal de".to_string(),
+        };
+        let invalid = SearchRequest {
+            query: "journal%".to_string(),
+        };
+
+        // WHEN
+        let valid_errors = valid.validate();
+        let invalid_errors = invalid.validate();
+
+        // THEN
+        assert!(valid_errors.is_empty());
+        assert_eq!(
+            invalid_errors,
+            vec![FieldError::new(
+                "query",
+                "only lower case letters and spaces are allowed"
+            )]
+        );
+    }
+}
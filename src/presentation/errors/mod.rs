@@ -0,0 +1,183 @@
+use crate::domain::errors::RepositoryError;
+use axum::Json;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+///
+/// Errors answered to the clients of the API, as a JSON problem (RFC 9457)
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// The request could not be read
+    BadRequest(String),
+    /// The request is missing fields or has invalid ones
+    InvalidFields(Vec<FieldError>),
+    /// The requested resource does not exist
+    NotFound(String),
+    /// The storage cannot be reached for now, the client may retry later
+    Unavailable,
+    /// The request failed for an unexpected reason
+    Internal,
+}
+
+///
+/// A missing or invalid field of a request
+///
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    /// The name of the field, as sent by the client
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> FieldError {
+        FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Problem {
+    #[serde(rename = "type")]
+    problem_type: &'static str,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
+}
+
+impl AppError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::InvalidFields(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn detail(&self) -> String {
+        match self {
+            AppError::BadRequest(detail) | AppError::NotFound(detail) => detail.clone(),
+            AppError::InvalidFields(_) => "The request has missing or invalid fields".to_string(),
+            AppError::Unavailable => "The database is unreachable, retry later".to_string(),
+            AppError::Internal => "The request could not be processed".to_string(),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let detail = self.detail();
+        let problem = Problem {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail,
+            errors: match self {
+                AppError::InvalidFields(errors) => errors,
+                _ => vec![],
+            },
+        };
+        (
+            status,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            Json(problem),
+        )
+            .into_response()
+    }
+}
+
+///
+/// The details of database errors are logged, not sent to the clients
+///
+impl From<RepositoryError> for AppError {
+    fn from(e: RepositoryError) -> Self {
+        eprintln!("Repository error: {}", e);
+        match e {
+            RepositoryError::Unavailable(_) => AppError::Unavailable,
+            RepositoryError::Failed(_) => AppError::Internal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    #[tokio::test]
+    async fn test_not_found_problem() {
+        // GIVEN
+        let error = AppError::NotFound("No programme on air on channel 'TF1.fr'".to_string());
+
+        // WHEN
+        let response = error.into_response();
+
+        // THEN
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            problem,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "No programme on air on channel 'TF1.fr'",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_fields_problem() {
+        // GIVEN
+        let error = AppError::InvalidFields(vec![FieldError::new("channelId", "is required")]);
+
+        // WHEN
+        let response = error.into_response();
+
+        // THEN
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            problem["errors"],
+            serde_json::json!([{ "field": "channelId", "message": "is required" }])
+        );
+    }
+
+    #[test]
+    fn test_unavailable_storage() {
+        // GIVEN
+        let error = RepositoryError::Unavailable("database is locked".to_string());
+
+        // WHEN
+        let app_error = AppError::from(error);
+
+        // THEN
+        assert_eq!(app_error, AppError::Unavailable);
+    }
+
+    #[test]
+    fn test_failed_repository_is_internal() {
+        // GIVEN
+        let error = RepositoryError::Failed("syntax error".to_string());
+
+        // WHEN
+        let app_error = AppError::from(error);
+
+        // THEN
+        assert_eq!(app_error, AppError::Internal);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::presentation::errors::{AppError, FieldError};
+use axum::Json;
+use axum::extract::{FromRequest, FromRequestParts, Query, Request};
+use axum::http::request::Parts;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+
+///
+/// Checks the fields of a request once deserialized
+///
+pub trait Validate {
+    /// Every missing or invalid field, none when the request is valid
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+///
+/// Query parameters deserialized into `T` and validated,
+/// rejected with a 400 problem listing the invalid fields otherwise
+///
+pub struct ValidQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| rejected(&rejection, rejection.body_text()))?;
+        validated(value).map(ValidQuery)
+    }
+}
+
+///
+/// JSON body deserialized into `T` and validated,
+/// rejected with a 400 problem listing the invalid fields otherwise
+///
+pub struct ValidJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| rejected(&rejection, rejection.body_text()))?;
+        validated(value).map(ValidJson)
+    }
+}
+
+///
+/// Name the field which could not be deserialized when there is one,
+/// otherwise the request as a whole is unreadable
+///
+fn rejected(rejection: &(dyn Error + 'static), detail: String) -> AppError {
+    let mut source = rejection.source();
+    while let Some(error) = source {
+        let field = if let Some(e) =
+            error.downcast_ref::<serde_path_to_error::Error<serde_urlencoded::de::Error>>()
+        {
+            Some((e.path().to_string(), e.inner().to_string()))
+        } else {
+            error
+                .downcast_ref::<serde_path_to_error::Error<serde_json::Error>>()
+                .map(|e| (e.path().to_string(), e.inner().to_string()))
+        };
+        match field {
+            // The path of the document itself
+            Some((path, _)) if path == "." => break,
+            Some((path, message)) => {
+                return AppError::InvalidFields(vec![FieldError::new(&path, &message)]);
+            }
+            None => source = error.source(),
+        }
+    }
+    AppError::BadRequest(detail)
+}
+
+fn validated<T: Validate>(value: T) -> Result<T, AppError> {
+    let errors = value.validate();
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(AppError::InvalidFields(errors))
+    }
+}
+
+///
+/// Report `field` as missing when its value is blank
+///
+pub fn require(field: &str, value: &str, errors: &mut Vec<FieldError>) {
+    if value.trim().is_empty() {
+        errors.push(FieldError::new(field, "is required"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presentation::dtos::ImportsQuery;
+    use axum::http;
+
+    async fn extract(uri: &str) -> Result<ImportsQuery, AppError> {
+        let (mut parts, _) = http::Request::builder()
+            .uri(uri)
+            .body(())
+            .unwrap()
+            .into_parts();
+        ValidQuery::<ImportsQuery>::from_request_parts(&mut parts, &())
+            .await
+            .map(|ValidQuery(query)| query)
+    }
+
+    #[tokio::test]
+    async fn test_valid_query() {
+        // GIVEN
+        let uri = "/admin/imports?limit=5";
+
+        // WHEN
+        let query = extract(uri).await;
+
+        // THEN
+        assert_eq!(query.map(|q| q.limit), Ok(Some(5)));
+    }
+
+    #[tokio::test]
+    async fn test_query_field_of_the_wrong_type() {
+        // GIVEN
+        let uri = "/admin/imports?limit=ten";
+
+        // WHEN
+        let query = extract(uri).await;
+
+        // THEN
+        assert_eq!(
+            query.map(|q| q.limit),
+            Err(AppError::InvalidFields(vec![FieldError::new(
+                "limit",
+                "invalid digit found in string"
+            )]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_field_failing_validation() {
+        // GIVEN
+        let uri = "/admin/imports?limit=0";
+
+        // WHEN
+        let query = extract(uri).await;
+
+        // THEN
+        assert_eq!(
+            query.map(|q| q.limit),
+            Err(AppError::InvalidFields(vec![FieldError::new(
+                "limit",
+                "must be positive"
+            )]))
+        );
+    }
+}
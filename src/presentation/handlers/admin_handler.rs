@@ -0,0 +1,62 @@
+use crate::data::repositories::import_repository;
+use crate::domain::entities::import_report::ImportReport;
+use crate::domain::entities::refresh_job::{RefreshJob, RefreshTrigger};
+use crate::domain::entities::refresh_status::RefreshStatus;
+use crate::presentation::dtos::ImportsQuery;
+use crate::presentation::errors::AppError;
+use crate::presentation::extractors::ValidQuery;
+use crate::presentation::state::AppState;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+
+pub async fn get_refresh_status(State(state): State<AppState>) -> Json<RefreshStatus> {
+    Json(state.scheduler.status())
+}
+
+///
+/// Start a refresh in the background. Answers 409 with the running job
+/// if a refresh is already in progress.
+///
+pub async fn trigger_refresh(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Option<RefreshJob>>) {
+    match state
+        .scheduler
+        .start_refresh(state.storage, RefreshTrigger::Manual)
+    {
+        Ok(job) => (StatusCode::ACCEPTED, Json(Some(job))),
+        Err(running_job_id) => (
+            StatusCode::CONFLICT,
+            Json(running_job_id.and_then(|id| state.scheduler.job(&id))),
+        ),
+    }
+}
+
+pub async fn get_refresh_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Result<Json<RefreshJob>, AppError> {
+    state
+        .scheduler
+        .job(&job_id)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("No refresh job '{}'", job_id)))
+}
+
+pub async fn get_imports(
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<ImportsQuery>,
+) -> Result<Json<Vec<ImportReport>>, AppError> {
+    Ok(Json(
+        import_repository::get_imports(&*state.storage.imports, query.limit).await?,
+    ))
+}
+
+pub async fn get_latest_import(
+    State(state): State<AppState>,
+) -> Result<Json<Option<ImportReport>>, AppError> {
+    Ok(Json(
+        import_repository::get_latest_import(&*state.storage.imports).await?,
+    ))
+}
@@ -1,8 +1,20 @@
 use crate::data::repositories::channel_repository;
 use crate::domain::entities::channel::Channel;
-use axum::extract::Path;
+use crate::presentation::dtos::{Page, PageQuery};
+use crate::presentation::errors::AppError;
+use crate::presentation::extractors::ValidQuery;
+use crate::presentation::state::AppState;
 use axum::Json;
+use axum::extract::{Path, State};
 
-pub async fn get_channels_by_package(Path(package): Path<String>) -> Json<Vec<Channel>> {
-    Json(channel_repository::get_channels_by_package(package))
-}
\ No newline at end of file
+pub async fn get_channels_by_package(
+    State(state): State<AppState>,
+    Path(package): Path<String>,
+    ValidQuery(page): ValidQuery<PageQuery>,
+) -> Result<Json<Page<Channel>>, AppError> {
+    let page = page.request();
+    let channels =
+        channel_repository::get_channels_by_package(&*state.storage.channels, package, page)
+            .await?;
+    Ok(Json(Page::new(channels, page)))
+}
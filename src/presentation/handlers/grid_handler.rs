@@ -0,0 +1,28 @@
+use crate::data::repositories::grid_repository;
+use crate::presentation::dtos::{Grid, GridQuery, PageQuery, WindowQuery};
+use crate::presentation::errors::AppError;
+use crate::presentation::extractors::ValidQuery;
+use crate::presentation::state::AppState;
+use axum::Json;
+use axum::extract::State;
+
+pub async fn get_grid(
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<GridQuery>,
+    ValidQuery(window): ValidQuery<WindowQuery>,
+    ValidQuery(page): ValidQuery<PageQuery>,
+) -> Result<Json<Grid>, AppError> {
+    let window = window
+        .grid_window(&state.prime_time.tz)
+        .map_err(|error| AppError::InvalidFields(vec![error]))?;
+    let page = page.request();
+    let channels = grid_repository::get_grid(
+        &*state.storage.channels,
+        &*state.storage.programs,
+        query.channels(),
+        window,
+        page,
+    )
+    .await?;
+    Ok(Json(Grid::new(channels, window, page)))
+}
@@ -1,2 +1,5 @@
+pub mod admin_handler;
 pub mod channels_handler;
+pub mod grid_handler;
+pub mod packages_handler;
 pub mod programs_handler;
@@ -0,0 +1,26 @@
+use crate::data::repositories::{now_repository, package_repository};
+use crate::domain::entities::package::Package;
+use crate::presentation::dtos::{ChannelNow, NowQuery, Page, PageQuery};
+use crate::presentation::errors::AppError;
+use crate::presentation::extractors::ValidQuery;
+use crate::presentation::state::AppState;
+use axum::Json;
+use axum::extract::{Path, State};
+
+pub async fn get_packages(State(state): State<AppState>) -> Json<Vec<Package>> {
+    Json(package_repository::get_packages(&state.packages))
+}
+
+pub async fn get_package_now(
+    State(state): State<AppState>,
+    Path(package): Path<String>,
+    ValidQuery(query): ValidQuery<NowQuery>,
+    ValidQuery(page): ValidQuery<PageQuery>,
+) -> Result<Json<Page<ChannelNow>>, AppError> {
+    let page = page.request();
+    let now = chrono::Utc::now();
+    let channels =
+        now_repository::get_package_now(&*state.storage.programs, package, now, query.next, page)
+            .await?;
+    Ok(Json(ChannelNow::page(channels, now, page)))
+}
@@ -1,49 +1,86 @@
 use crate::data::repositories::program_repository;
 use crate::domain::entities::program::Program;
-use crate::presentation::dtos::Page;
+use crate::presentation::dtos::{
+    ChannelQuery, Page, PageQuery, SearchRequest, Tonight, TonightParts, TonightQuery, WindowQuery,
+};
+use crate::presentation::errors::AppError;
+use crate::presentation::extractors::{ValidJson, ValidQuery};
+use crate::presentation::state::AppState;
 use axum::Json;
-use axum::extract::Query;
-use std::collections::HashMap;
+use axum::extract::State;
+use chrono::Utc;
 
 pub async fn get_programs_by_channel_id(
-    Query(params): Query<HashMap<String, String>>,
-) -> Json<Page<Program>> {
-    let channel_id = match params.get("channelId") {
-        Some(id) => id.clone(),
-        None => return Json(Page { content: vec![] }),
-    };
-    Json(program_repository::get_programs_by_channel_id(channel_id))
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<ChannelQuery>,
+    ValidQuery(window): ValidQuery<WindowQuery>,
+    ValidQuery(page): ValidQuery<PageQuery>,
+) -> Result<Json<Page<Program>>, AppError> {
+    let page = page.request();
+    let programs = program_repository::get_programs_by_channel_id(
+        &*state.storage.programs,
+        query.channel_id,
+        window.window(&state.prime_time.tz),
+        page,
+    )
+    .await?;
+    Ok(Json(Page::new(programs, page)))
 }
 
 pub async fn get_current_program_by_channel_id(
-    Query(params): Query<HashMap<String, String>>,
-) -> Json<Option<Program>> {
-    let channel_id = match params.get("channelId") {
-        Some(id) => id.clone(),
-        None => return Json(None),
-    };
-    let program = program_repository::get_current_program_by_channel_id(channel_id);
-    Json(Some(program))
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<ChannelQuery>,
+) -> Result<Json<Program>, AppError> {
+    let channel_id = query.channel_id;
+    program_repository::get_current_program_by_channel_id(
+        &*state.storage.programs,
+        channel_id.clone(),
+    )
+    .await?
+    .map(Json)
+    .ok_or_else(|| AppError::NotFound(format!("No programme on air on channel '{}'", channel_id)))
 }
 
 pub async fn get_tonight_program_by_channel_id(
-    Query(params): Query<HashMap<String, String>>,
-) -> Json<Option<Program>> {
-    let channel_id = match params.get("channelId") {
-        Some(id) => id.clone(),
-        None => return Json(None),
-    };
-    let program = program_repository::get_tonight_program_by_channel_id(channel_id);
-    Json(Some(program))
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<ChannelQuery>,
+    ValidQuery(tonight): ValidQuery<TonightQuery>,
+) -> Result<Json<Tonight>, AppError> {
+    let channel_id = query.channel_id;
+    let date = tonight
+        .date
+        .unwrap_or_else(|| state.prime_time.tonight(Utc::now()));
+    let mut programs = program_repository::get_tonight_programs_by_channel_id(
+        &*state.storage.programs,
+        channel_id.clone(),
+        &state.prime_time,
+        date,
+        tonight.parts.count(),
+    )
+    .await?
+    .into_iter();
+    let first_part = programs.next().ok_or_else(|| {
+        AppError::NotFound(format!(
+            "No programme on the evening of {} on channel '{}'",
+            date, channel_id
+        ))
+    })?;
+    Ok(Json(match tonight.parts {
+        TonightParts::First => Tonight::FirstPart(first_part),
+        TonightParts::Both => Tonight::BothParts {
+            first_part,
+            second_part: programs.next().map(Box::new),
+        },
+    }))
 }
 
-pub async fn search_programs(Json(payload): Json<HashMap<String, String>>) -> Json<Vec<Program>> {
-    let query = match payload.get("query") {
-        Some(q) => q.clone(),
-        None => return Json(vec![]),
-    };
-    if query.trim().is_empty() {
-        return Json(vec![]);
-    }
-    Json(program_repository::search_programs(query))
+pub async fn search_programs(
+    State(state): State<AppState>,
+    ValidQuery(page): ValidQuery<PageQuery>,
+    ValidJson(request): ValidJson<SearchRequest>,
+) -> Result<Json<Page<Program>>, AppError> {
+    let page = page.request();
+    let programs =
+        program_repository::search_programs(&*state.storage.programs, request.query, page).await?;
+    Ok(Json(Page::new(programs, page)))
 }
@@ -1,3 +1,7 @@
 pub mod dtos;
+pub mod errors;
+pub mod extractors;
 pub mod handlers;
+pub mod middlewares;
 pub mod routes;
+pub mod state;
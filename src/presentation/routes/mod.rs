@@ -1,17 +1,42 @@
+use crate::presentation::handlers::admin_handler::{
+    get_imports, get_latest_import, get_refresh_job, get_refresh_status, trigger_refresh,
+};
 use crate::presentation::handlers::channels_handler::get_channels_by_package;
+use crate::presentation::handlers::grid_handler::get_grid;
+use crate::presentation::handlers::packages_handler::{get_package_now, get_packages};
 use crate::presentation::handlers::programs_handler::{
     get_current_program_by_channel_id, get_programs_by_channel_id,
     get_tonight_program_by_channel_id, search_programs,
 };
+use crate::presentation::middlewares::admin_auth::require_admin_token;
+use crate::presentation::state::AppState;
+use axum::middleware;
 use axum::routing::post;
 use axum::{Router, routing::get};
 
-pub fn create_router() -> Router {
+pub fn create_router(state: AppState) -> Router {
     Router::new()
+        .route("/packages", get(get_packages))
+        .route("/packages/{package}/now", get(get_package_now))
         .route("/channels/{package}", get(get_channels_by_package))
         .route("/programs", get(get_programs_by_channel_id))
         .route("/programs/current", get(get_current_program_by_channel_id))
         .route("/programs/tonight", get(get_tonight_program_by_channel_id))
         .route("/programs/search", post(search_programs))
+        .route("/grid", get(get_grid))
+        .nest("/admin", admin_router())
         .fallback(get(|| async { "Not Found" }))
+        .with_state(state)
+}
+
+///
+/// Every admin route requires the admin token
+///
+fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/refresh", get(get_refresh_status).post(trigger_refresh))
+        .route("/refresh/{job_id}", get(get_refresh_job))
+        .route("/imports", get(get_imports))
+        .route("/imports/latest", get(get_latest_import))
+        .route_layer(middleware::from_fn(require_admin_token))
 }
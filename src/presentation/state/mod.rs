@@ -0,0 +1,20 @@
+use crate::config::packages::PackagesConfig;
+use crate::config::prime_time::PrimeTime;
+use crate::data::repositories::storage::Storage;
+use crate::scheduler::Scheduler;
+use std::sync::Arc;
+
+///
+/// Shared by every request handler
+///
+#[derive(Clone)]
+pub struct AppState {
+    /// The repositories of the configured storage backend
+    pub storage: Storage,
+    /// When the programmes of the evening start
+    pub prime_time: PrimeTime,
+    /// The packages configuration, loaded at startup
+    pub packages: Arc<PackagesConfig>,
+    /// The XMLTV refreshes, their status and latest jobs
+    pub scheduler: Scheduler,
+}
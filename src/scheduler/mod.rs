@@ -0,0 +1,330 @@
+use crate::data::repositories::storage::Storage;
+use crate::data::repositories::xml_tv_repository;
+use crate::domain::entities::refresh_job::{RefreshJob, RefreshJobStatus, RefreshTrigger};
+use crate::domain::entities::refresh_status::RefreshStatus;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use dotenv::var;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Number of finished jobs kept in memory for polling
+const MAX_JOBS: usize = 20;
+
+#[derive(Default)]
+struct SchedulerState {
+    status: RefreshStatus,
+    jobs: VecDeque<RefreshJob>,
+}
+
+///
+/// Runs the XMLTV refreshes and keeps their status and latest jobs,
+/// shared by the clones given to the request handlers
+///
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+///
+/// When the XMLTV data should be refreshed in the background
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum RefreshSchedule {
+    /// Data is only loaded at startup
+    Disabled,
+    /// Data is refreshed every given duration
+    Every(Duration),
+    /// Data is refreshed every day at the given local time
+    DailyAt(NaiveTime),
+}
+
+impl RefreshSchedule {
+    ///
+    /// Read the schedule from the `REFRESH_DAILY_AT` (HH:MM) or
+    /// `REFRESH_INTERVAL_MINUTES` environment variables
+    ///
+    pub fn from_env() -> RefreshSchedule {
+        if let Ok(daily_at) = var("REFRESH_DAILY_AT") {
+            match NaiveTime::parse_from_str(daily_at.trim(), "%H:%M") {
+                Ok(time) => return RefreshSchedule::DailyAt(time),
+                Err(e) => eprintln!("Invalid REFRESH_DAILY_AT value {}: {}", daily_at, e),
+            }
+        }
+        if let Ok(interval) = var("REFRESH_INTERVAL_MINUTES") {
+            match interval.trim().parse::<u64>() {
+                Ok(minutes) if minutes > 0 => {
+                    return RefreshSchedule::Every(Duration::from_secs(minutes * 60));
+                }
+                _ => eprintln!("Invalid REFRESH_INTERVAL_MINUTES value {}", interval),
+            }
+        }
+        RefreshSchedule::Disabled
+    }
+
+    ///
+    /// Compute the next run time strictly after `now`
+    ///
+    pub fn next_run(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            RefreshSchedule::Disabled => None,
+            RefreshSchedule::Every(interval) => {
+                chrono::Duration::from_std(*interval).ok().map(|d| now + d)
+            }
+            RefreshSchedule::DailyAt(time) => {
+                let mut day = now.date_naive();
+                // Look a couple of days ahead in case the time does not exist (DST gap)
+                for _ in 0..3 {
+                    if let Some(candidate) =
+                        Local.from_local_datetime(&day.and_time(*time)).earliest()
+                        && candidate > now
+                    {
+                        return Some(candidate);
+                    }
+                    day = day.succ_opt()?;
+                }
+                None
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RefreshSchedule::Disabled => "disabled".to_string(),
+            RefreshSchedule::Every(interval) => {
+                format!("every {} minutes", interval.as_secs() / 60)
+            }
+            RefreshSchedule::DailyAt(time) => format!("daily at {}", time.format("%H:%M")),
+        }
+    }
+}
+
+impl Scheduler {
+    ///
+    /// Get a snapshot of the refresh status
+    ///
+    pub fn status(&self) -> RefreshStatus {
+        self.state
+            .lock()
+            .map(|s| s.status.clone())
+            .unwrap_or_default()
+    }
+
+    ///
+    /// Get a refresh job by id
+    ///
+    pub fn job(&self, job_id: &str) -> Option<RefreshJob> {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|s| s.jobs.iter().find(|j| j.job_id == job_id).cloned())
+    }
+
+    fn update_state(&self, f: impl FnOnce(&mut SchedulerState)) {
+        if let Ok(mut state) = self.state.lock() {
+            f(&mut state);
+        }
+    }
+
+    fn update_job(&self, job_id: &str, f: impl FnOnce(&mut RefreshJob)) {
+        self.update_state(|s| {
+            if let Some(job) = s.jobs.iter_mut().find(|j| j.job_id == job_id) {
+                f(job);
+            }
+        });
+    }
+
+    ///
+    /// Register a new running job, unless another one is already running.
+    /// Returns the id of the running job as error in that case.
+    ///
+    fn claim_job(&self, trigger: RefreshTrigger) -> Result<RefreshJob, Option<String>> {
+        let mut state = self.state.lock().map_err(|_| None)?;
+        if let Some(current_job_id) = &state.status.current_job_id {
+            return Err(Some(current_job_id.clone()));
+        }
+
+        let job = RefreshJob {
+            job_id: uuid::Uuid::new_v4().to_string(),
+            trigger,
+            status: RefreshJobStatus::Running,
+            progress: "Starting".to_string(),
+            started_at: Local::now(),
+            finished_at: None,
+            report: None,
+        };
+        state.status.running = true;
+        state.status.current_job_id = Some(job.job_id.clone());
+        state.status.last_run_at = Some(job.started_at);
+        if state.jobs.len() >= MAX_JOBS {
+            state.jobs.pop_front();
+        }
+        state.jobs.push_back(job.clone());
+        Ok(job)
+    }
+
+    async fn run_job(self, storage: Storage, job_id: String) -> bool {
+        let progress_job_id = job_id.clone();
+        let scheduler = self.clone();
+        let progress = move |step: &str| {
+            scheduler.update_job(&progress_job_id, |job| job.progress = step.to_string());
+        };
+
+        // Run in its own task so an unexpected panic does not take the caller down
+        let report =
+            match tokio::spawn(xml_tv_repository::init_xml_tv_data(storage, progress)).await {
+                Ok(report) => Some(report),
+                Err(e) => {
+                    eprintln!("XML TV data refresh failed: {}", e);
+                    None
+                }
+            };
+        let success = report.as_ref().is_some_and(|r| r.succeeded());
+
+        self.update_state(|s| {
+            let now = Local::now();
+            s.status.running = false;
+            s.status.current_job_id = None;
+            if success {
+                s.status.last_success_at = Some(now);
+            } else {
+                s.status.last_failure_at = Some(now);
+            }
+            if let Some(job) = s.jobs.iter_mut().find(|j| j.job_id == job_id) {
+                job.status = if success {
+                    RefreshJobStatus::Succeeded
+                } else {
+                    RefreshJobStatus::Failed
+                };
+                job.progress = "Finished".to_string();
+                job.finished_at = Some(now);
+                job.report = report;
+            }
+        });
+        success
+    }
+
+    ///
+    /// Run the whole XMLTV ingestion once and wait for its outcome.
+    /// Returns true if the refresh succeeded, false if it failed or
+    /// another refresh was already running.
+    ///
+    pub async fn run_refresh(&self, storage: Storage, trigger: RefreshTrigger) -> bool {
+        match self.claim_job(trigger) {
+            Ok(job) => self.clone().run_job(storage, job.job_id).await,
+            Err(_) => {
+                println!("A refresh is already running, skipping this one.");
+                false
+            }
+        }
+    }
+
+    ///
+    /// Start the XMLTV ingestion in the background.
+    /// Returns the new job, or the id of the running one as error if a refresh is already running.
+    ///
+    pub fn start_refresh(
+        &self,
+        storage: Storage,
+        trigger: RefreshTrigger,
+    ) -> Result<RefreshJob, Option<String>> {
+        let job = self.claim_job(trigger)?;
+        tokio::spawn(self.clone().run_job(storage, job.job_id.clone()));
+        Ok(job)
+    }
+
+    ///
+    /// Start the background refresh loop according to the given schedule
+    ///
+    pub fn start(&self, storage: Storage, schedule: RefreshSchedule) {
+        println!("XML TV refresh schedule: {}", schedule.describe());
+        self.update_state(|s| s.status.schedule = schedule.describe());
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            while let Some(next_run) = schedule.next_run(Local::now()) {
+                scheduler.update_state(|s| s.status.next_run_at = Some(next_run));
+                let wait = (next_run - Local::now()).to_std().unwrap_or_default();
+                tokio::time::sleep(wait).await;
+                println!("Starting scheduled XML TV refresh...");
+                scheduler
+                    .run_refresh(storage.clone(), RefreshTrigger::Schedule)
+                    .await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_run_every() {
+        // GIVEN
+        let schedule = RefreshSchedule::Every(Duration::from_secs(90 * 60));
+        let now = Local.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+
+        // WHEN
+        let next = schedule.next_run(now);
+
+        // THEN
+        assert_eq!(
+            next,
+            Some(Local.with_ymd_and_hms(2024, 1, 1, 11, 30, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_next_run_daily_later_today() {
+        // GIVEN
+        let schedule = RefreshSchedule::DailyAt(NaiveTime::from_hms_opt(4, 30, 0).unwrap());
+        let now = Local.with_ymd_and_hms(2024, 1, 1, 3, 0, 0).unwrap();
+
+        // WHEN
+        let next = schedule.next_run(now);
+
+        // THEN
+        assert_eq!(
+            next,
+            Some(Local.with_ymd_and_hms(2024, 1, 1, 4, 30, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_next_run_daily_tomorrow() {
+        // GIVEN
+        let schedule = RefreshSchedule::DailyAt(NaiveTime::from_hms_opt(4, 30, 0).unwrap());
+        let now = Local.with_ymd_and_hms(2024, 1, 1, 4, 30, 0).unwrap();
+
+        // WHEN
+        let next = schedule.next_run(now);
+
+        // THEN
+        assert_eq!(
+            next,
+            Some(Local.with_ymd_and_hms(2024, 1, 2, 4, 30, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_next_run_disabled() {
+        assert_eq!(RefreshSchedule::Disabled.next_run(Local::now()), None);
+    }
+
+    #[test]
+    fn test_schedulers_do_not_share_jobs() {
+        // GIVEN
+        let scheduler = Scheduler::default();
+        let other = Scheduler::default();
+
+        // WHEN
+        let job = scheduler.claim_job(RefreshTrigger::Manual).unwrap();
+
+        // THEN
+        assert!(scheduler.status().running);
+        assert!(scheduler.job(&job.job_id).is_some());
+        assert!(!other.status().running);
+        assert!(other.job(&job.job_id).is_none());
+        assert!(other.claim_job(RefreshTrigger::Manual).is_ok());
+    }
+}